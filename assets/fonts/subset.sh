@@ -0,0 +1,13 @@
+#!/usr/bin/env bash
+# Download Noto Sans SC and write the subset font embedded by the `bundled-font` feature.
+# Needs curl and fonttools (`pip install fonttools`).
+set -eux
+
+cd "$(dirname "$0")"
+
+curl -fsSL -o NotoSansSC-Regular.otf \
+    https://github.com/notofonts/noto-cjk/raw/main/Sans/SubsetOTF/SC/NotoSansSC-Regular.otf
+pyftsubset NotoSansSC-Regular.otf \
+    --unicodes="U+0020-007E,U+3000-303F,U+FF00-FFEF,U+4E00-9FFF" \
+    --output-file=NotoSansSC-subset.otf
+rm NotoSansSC-Regular.otf
@@ -0,0 +1,241 @@
+//! 各个转换的吞吐量，输入是内存里生成的数据，不包含磁盘读写
+//!
+//! `cargo bench` 运行，结果在 `target/criterion` 里，可以和上一次的结果比较。
+
+#[cfg(not(target_arch = "wasm32"))]
+// 一个工具都不编译时没有可测的转换
+#[cfg_attr(
+    not(any(
+        feature = "logic",
+        feature = "hardfault",
+        feature = "hci",
+        feature = "analyze",
+        feature = "convert"
+    )),
+    allow(unused_imports, dead_code)
+)]
+mod converters {
+    use criterion::{criterion_group, Criterion, Throughput};
+
+    /// 每个输入大约的字节数
+    const INPUT_SIZE: usize = 4 << 20;
+
+    /// 按 Kingst 导出格式生成 csv，`row` 生成第 i 行
+    #[cfg(any(feature = "logic", feature = "analyze"))]
+    fn kingst_csv(header: &str, mut row: impl FnMut(usize) -> String) -> Vec<u8> {
+        let mut csv = format!("{}\r\n", header);
+        let mut i = 0;
+        while csv.len() < INPUT_SIZE {
+            csv.push_str(&row(i));
+            csv.push_str("\r\n");
+            i += 1;
+        }
+        csv.into_bytes()
+    }
+
+    #[cfg(any(feature = "logic", feature = "hardfault"))]
+    fn lcg(i: usize) -> u32 {
+        (i as u32).wrapping_mul(1_103_515_245).wrapping_add(12345) >> 8
+    }
+
+    #[cfg(feature = "logic")]
+    fn logic(c: &mut Criterion) {
+        use std::io::sink;
+
+        use worktool::core::logic::{
+            iis, spi_bluetrum, spi_raw, spi_txt, uart, LogicIISArgs, LogicUARTArgs,
+            KINGST_IIS_FILE_FORMAT, KINGST_SPI_FILE_FORMAT, KINGST_UART_FILE_FORMAT,
+        };
+
+        let time = |i: usize| i as f64 * 0.0000125;
+        let spi = kingst_csv(KINGST_SPI_FILE_FORMAT, |i| {
+            format!("{:.9},0,0x{:02X},", time(i), lcg(i) as u8)
+        });
+        // 蓝讯 DUMP 每帧 8 字节帧头加 256 字节数据，帧类型轮流 0/2/4
+        let bluetrum = kingst_csv(KINGST_SPI_FILE_FORMAT, |i| {
+            let offset = i % 264;
+            let byte = match offset {
+                4 => ((i / 264) % 3 * 2) as u8,
+                5 => 0x01,
+                6 => 0x00,
+                0..=7 => 0,
+                _ => lcg(i) as u8,
+            };
+            format!("{:.9},0,0x{:02X},", time(i), byte)
+        });
+        let iis_csv = kingst_csv(KINGST_IIS_FILE_FORMAT, |i| {
+            format!("{:.9},{},0x{:04X}", time(i), i % 2, lcg(i) as u16)
+        });
+        let uart_csv = kingst_csv(KINGST_UART_FILE_FORMAT, |i| {
+            format!("{:.9},0x{:02X},,", time(i), lcg(i) as u8)
+        });
+
+        let mut group = c.benchmark_group("logic");
+        group.throughput(Throughput::Bytes(spi.len() as u64));
+        group.bench_function("spi_raw", |b| {
+            b.iter(|| spi_raw(spi.as_slice(), &mut sink()).unwrap())
+        });
+        group.bench_function("spi_txt", |b| {
+            b.iter(|| spi_txt(spi.as_slice(), &mut sink()).unwrap())
+        });
+        group.throughput(Throughput::Bytes(bluetrum.len() as u64));
+        group.bench_function("spi_bluetrum", |b| {
+            b.iter(|| spi_bluetrum(bluetrum.as_slice(), &mut [sink(), sink(), sink()]).unwrap())
+        });
+        group.throughput(Throughput::Bytes(iis_csv.len() as u64));
+        group.bench_function("iis", |b| {
+            b.iter(|| iis(&LogicIISArgs::default(), iis_csv.as_slice(), &mut sink()).unwrap())
+        });
+        group.throughput(Throughput::Bytes(uart_csv.len() as u64));
+        group.bench_function("uart", |b| {
+            b.iter(|| uart(&LogicUARTArgs::default(), uart_csv.as_slice(), &mut sink()).unwrap())
+        });
+        group.finish();
+    }
+
+    #[cfg(feature = "hardfault")]
+    fn hardfault(c: &mut Criterion) {
+        use worktool::core::hardfault::parse;
+
+        // 普通打印里夹着寄存器组
+        let mut log = String::new();
+        let mut i = 0;
+        while log.len() < INPUT_SIZE {
+            log.push_str(&format!("[00:00:{:02}.000] task {} running\r\n", i % 60, i));
+            if i % 100 == 0 {
+                log.push_str("ERR: 0x00000002 EPC: 0x10001234\r\n");
+                for row in 0..4 {
+                    let words: Vec<String> = (0..8)
+                        .map(|n| format!("{:08x}", lcg(i + row * 8 + n)))
+                        .collect();
+                    log.push_str(&words.join(" "));
+                    log.push_str("\r\n");
+                }
+            }
+            i += 1;
+        }
+
+        let mut group = c.benchmark_group("hardfault");
+        group.throughput(Throughput::Bytes(log.len() as u64));
+        group.bench_function("parse", |b| b.iter(|| parse(log.as_bytes()).unwrap()));
+        group.finish();
+    }
+
+    #[cfg(feature = "hci")]
+    fn hci(c: &mut Criterion) {
+        use std::io::sink;
+
+        use worktool::core::encoding::decode_stream;
+        use worktool::core::hci::convert_stream;
+
+        let mut log = String::new();
+        let mut i = 0;
+        while log.len() < INPUT_SIZE {
+            let time = format!("00:00:{:02}.{:03}", i / 1000 % 60, i % 1000);
+            match i % 4 {
+                0 => log.push_str(&format!("({}) [{}]CMD => 01 03 0C 00\r\n", time, time)),
+                1 => log.push_str(&format!("[{}]EVT <= 04 0E 04 01 03 0C 00\r\n", time)),
+                2 => log.push_str(&format!("[{}]ACL => 02 40 20 05 00 01 00\r\n", time)),
+                _ => log.push_str(&format!("[{}]蓝牙调试打印 {}\r\n", time, i)),
+            }
+            i += 1;
+        }
+        let (gbk, _, _) = encoding_rs::GBK.encode(&log);
+
+        let mut group = c.benchmark_group("hci");
+        group.throughput(Throughput::Bytes(log.len() as u64));
+        group.bench_function("btsnoop", |b| {
+            b.iter(|| convert_stream(log.as_bytes(), &mut sink()).unwrap())
+        });
+        group.throughput(Throughput::Bytes(gbk.len() as u64));
+        group.bench_function("decode_gbk", |b| {
+            b.iter(|| decode_stream(encoding_rs::GBK.into(), gbk.as_ref(), &mut sink()).unwrap())
+        });
+        group.finish();
+    }
+
+    #[cfg(feature = "analyze")]
+    fn analyze(c: &mut Criterion) {
+        use std::io::sink;
+
+        use worktool::core::analyze::analyze;
+
+        let messages = ["A:boot ok", "B:temp 25", "noise", "C:ignored"];
+        let csv = kingst_csv("Time [s],Packet ID,MOSI,MISO", |i| {
+            let message = messages[i / 16 % messages.len()].as_bytes();
+            let c = match message.get(i % 16) {
+                Some(b' ') => "NUL".to_string(),
+                Some(&c) => (c as char).to_string(),
+                None if i % 16 == 15 => "LF ".to_string(),
+                None => "NUL".to_string(),
+            };
+            format!("{:.9},0,{},", i as f64 * 0.0000125, c)
+        });
+
+        let mut group = c.benchmark_group("analyze");
+        group.throughput(Throughput::Bytes(csv.len() as u64));
+        group.bench_function("analyze", |b| {
+            b.iter(|| analyze("A,B", csv.as_slice(), &mut sink()).unwrap())
+        });
+        group.finish();
+    }
+
+    #[cfg(feature = "convert")]
+    fn convert(c: &mut Criterion) {
+        use std::io::sink;
+
+        use worktool::core::convert::{convert, ConvertOptions, LineEnding, TargetEncoding};
+
+        let mut text = String::new();
+        let mut i = 0;
+        while text.len() < INPUT_SIZE {
+            text.push_str(&format!("[00:00:{:02}.000]蓝牙调试打印 {}\r\n", i % 60, i));
+            i += 1;
+        }
+
+        let mut group = c.benchmark_group("convert");
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        for (name, target, line_ending) in [
+            ("utf8_lf", TargetEncoding::UTF8, LineEnding::Lf),
+            ("gbk", TargetEncoding::GBK, LineEnding::Keep),
+            ("utf16le", TargetEncoding::UTF16LE, LineEnding::Keep),
+        ] {
+            let options = ConvertOptions {
+                target,
+                line_ending,
+                ..Default::default()
+            };
+            group.bench_function(name, |b| {
+                b.iter(|| convert(text.as_bytes(), &mut sink(), &options).unwrap())
+            });
+        }
+        group.finish();
+    }
+
+    fn converters(c: &mut Criterion) {
+        #[cfg(feature = "logic")]
+        logic(c);
+        #[cfg(feature = "hardfault")]
+        hardfault(c);
+        #[cfg(feature = "hci")]
+        hci(c);
+        #[cfg(feature = "analyze")]
+        analyze(c);
+        #[cfg(feature = "convert")]
+        convert(c);
+        let _ = c;
+    }
+
+    criterion_group! {
+        name = benches;
+        config = Criterion::default().sample_size(20);
+        targets = converters
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+criterion::criterion_main!(converters::benches);
+
+/// criterion 只支持本地
+#[cfg(target_arch = "wasm32")]
+fn main() {}
@@ -1,21 +1,48 @@
-use std::process::Command;
-use std::io::Write;
 use chrono::SecondsFormat;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+
+/// `bundled-font` 打包的字体，仓库里没有，生成方法见 `assets/fonts/README.md`
+const BUNDLED_FONT: &str = "assets/fonts/NotoSansSC-subset.otf";
+
+/// 每个工具的 feature，新增工具时加在这里
+const TOOL_FEATURES: &[&str] = &["logic", "hardfault", "hci", "analyze", "convert"];
 
 fn main() {
+    // 至少编译了一个工具时设置 `any_tool`，文件列表、后台任务这些共用的代码用它开关
+    println!("cargo:rustc-check-cfg=cfg(any_tool)");
+    let feature_enabled = |feature: &str| {
+        std::env::var_os(format!("CARGO_FEATURE_{}", feature.to_uppercase())).is_some()
+    };
+    if TOOL_FEATURES.iter().any(|feature| feature_enabled(feature)) {
+        println!("cargo:rustc-cfg=any_tool");
+    }
+
+    if std::env::var_os("CARGO_FEATURE_BUNDLED_FONT").is_some() {
+        let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        if !Path::new(&manifest_dir).join(BUNDLED_FONT).is_file() {
+            panic!(
+                "the bundled-font feature needs {}, which is not checked in; \
+                 generate it as described in assets/fonts/README.md",
+                BUNDLED_FONT
+            );
+        }
+    }
+
     // 获取当前时间
     let compile_time = chrono::Local::now().to_rfc3339_opts(SecondsFormat::Secs, true);
 
     // 获取 git 提交哈希
     let git_hash = Command::new("git")
-        .args(&["rev-parse", "HEAD"])
+        .args(["rev-parse", "HEAD"])
         .output()
         .map(|output| String::from_utf8(output.stdout).unwrap())
         .unwrap_or_else(|_| "unknown".to_string());
 
     // 获取 git 提交时间
     let git_timestamp = Command::new("git")
-        .args(&["log", "-1", "--format=%cd", "--date=iso"])
+        .args(["log", "-1", "--format=%cd", "--date=iso"])
         .output()
         .map(|output| String::from_utf8(output.stdout).unwrap())
         .unwrap_or_else(|_| "unknown".to_string());
@@ -26,6 +53,11 @@ fn main() {
     let mut f = std::fs::File::create(&dest_path).unwrap();
 
     writeln!(f, "pub const GIT_HASH: &str = \"{}\";", git_hash.trim()).unwrap();
-    writeln!(f, "pub const GIT_TIMESTAMP: &str = \"{}\";", git_timestamp.trim()).unwrap();
+    writeln!(
+        f,
+        "pub const GIT_TIMESTAMP: &str = \"{}\";",
+        git_timestamp.trim()
+    )
+    .unwrap();
     writeln!(f, "pub const COMPILE_TIME: &str = \"{}\";", compile_time).unwrap();
 }
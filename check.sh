@@ -3,9 +3,10 @@
 set -eux
 
 cargo check --quiet --workspace --all-targets
-cargo check --quiet --workspace --all-features --lib --target wasm32-unknown-unknown
+cargo check --quiet --workspace --lib --bins --target wasm32-unknown-unknown
+cargo clippy --quiet --workspace --lib --bins --target wasm32-unknown-unknown -- -D warnings
 cargo fmt --all -- --check
-cargo clippy --quiet --workspace --all-targets --all-features --  -D warnings -W clippy::all
-cargo test --quiet --workspace --all-targets --all-features
+cargo clippy --quiet --workspace --all-targets --  -D warnings -W clippy::all
+cargo test --quiet --workspace --all-targets
 cargo test --quiet --workspace --doc
 trunk build
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use worktool::core::analyze::analyze;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = analyze("A,B", data, &mut std::io::sink());
+});
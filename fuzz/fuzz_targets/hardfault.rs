@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use worktool::core::hardfault::{parse, write_report};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(regs) = parse(data) {
+        let _ = write_report(&regs, std::io::sink());
+    }
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use worktool::core::hci::parse;
+
+fuzz_target!(|content: &str| {
+    for packet in parse(content) {
+        assert_eq!(packet.data[0], packet.packet_type as u8);
+    }
+});
@@ -0,0 +1,30 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use worktool::core::logic::{
+    iis, spi_bluetrum, spi_raw, spi_txt, uart, LogicIISArgs, LogicUARTArgs, KINGST_IIS_FILE_FORMAT,
+    KINGST_SPI_FILE_FORMAT, KINGST_UART_FILE_FORMAT,
+};
+
+// 第一个字节选择转换，后面的内容接在对应的表头后面
+fuzz_target!(|data: &[u8]| {
+    let Some((&which, body)) = data.split_first() else {
+        return;
+    };
+    let header = match which % 5 {
+        0..=2 => KINGST_SPI_FILE_FORMAT,
+        3 => KINGST_IIS_FILE_FORMAT,
+        _ => KINGST_UART_FILE_FORMAT,
+    };
+    let mut input = format!("{}\r\n", header).into_bytes();
+    input.extend_from_slice(body);
+
+    let mut out = Vec::new();
+    let _ = match which % 5 {
+        0 => spi_raw(input.as_slice(), &mut out),
+        1 => spi_txt(input.as_slice(), &mut out),
+        2 => spi_bluetrum(input.as_slice(), &mut [Vec::new(), Vec::new(), Vec::new()]),
+        3 => iis(&LogicIISArgs::default(), input.as_slice(), &mut out),
+        _ => uart(&LogicUARTArgs::default(), input.as_slice(), &mut out),
+    };
+});
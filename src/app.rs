@@ -1,173 +1,440 @@
-use std::collections::HashMap;
-use std::sync::Arc;
+use crate::component::{load_settings, path_edit, registry, Browse, Interface, ToolInfo};
+use crate::console::Console;
+use crate::font::{FontSettings, FONT_KEY};
+use crate::history::{History, RunRecord, HISTORY_KEY};
+use crate::i18n::{self, Language, LANGUAGE_KEY};
+use crate::tr;
+use crate::workspace::Workspace;
 
-use crate::component::{AnalyzeToolPage, HardfaultToolPage, HciToolPage, Interface, LogicToolPage};
+use std::path::Path;
 
 use egui::vec2;
-use egui::{ScrollArea, Ui};
-
-use num_enum::{IntoPrimitive, TryFromPrimitive};
+use egui::{ScrollArea, Ui, WidgetText};
+use egui_dock::{DockArea, DockState, Style, TabViewer};
 
 include!(concat!(env!("OUT_DIR"), "/info.rs"));
 
-#[derive(
-    serde::Deserialize, serde::Serialize, PartialEq, Eq, Hash, TryFromPrimitive, IntoPrimitive,
-)]
-#[repr(u32)]
-enum ActiveInterface {
+static DOCK_LAYOUT_KEY: &str = "DockLayout";
+static WORKSPACE_PATH_KEY: &str = "WorkspacePath";
+
+struct Tool {
+    info: ToolInfo,
+    page: Box<dyn Interface>,
+}
+
+/// 工作区里的标签页，工具用注册表里的 id 保存
+#[derive(Clone, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+enum Tab {
     Home,
-    LogicTool,
-    HardfaultTool,
-    HciTool,
-    AnalyzeTool,
+    History,
+    Console,
+    Tool(String),
 }
 
-impl ActiveInterface {
-    fn as_str(&self) -> &'static str {
-        match self {
-            ActiveInterface::Home => "Home",
-            ActiveInterface::HardfaultTool => "Hardfault Tool",
-            ActiveInterface::HciTool => "Hci Tool",
-            ActiveInterface::LogicTool => "Logic Tool",
-            ActiveInterface::AnalyzeTool => "Analyze Tool",
-        }
-    }
+/// 首页上点击的操作
+enum HomeAction {
+    Open(Tab),
+    SaveWorkspace,
+    OpenWorkspace,
+    RunAll,
+}
+
+/// 首页上的工作区文件路径和上一次操作的结果
+#[derive(Default)]
+struct WorkspaceState {
+    path: String,
+    status: Option<Result<String, String>>,
 }
 
 pub struct WorkToolApp {
-    interfaces: HashMap<ActiveInterface, Box<dyn Interface>>,
-    current_page: ActiveInterface,
+    tools: Vec<Tool>,
+    dock: DockState<Tab>,
+    history: History,
+    console: Console,
+    workspace: WorkspaceState,
+    font: FontSettings,
 }
 
 impl WorkToolApp {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        setup_custom_fonts(&cc.egui_ctx);
+        if let Some(language) = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, LANGUAGE_KEY))
+        {
+            i18n::set_language(language);
+        }
+
+        let font = FontSettings::new(
+            &cc.egui_ctx,
+            cc.storage
+                .and_then(|storage| eframe::get_value(storage, FONT_KEY))
+                .unwrap_or_default(),
+        );
+        let tools: Vec<Tool> = registry()
+            .into_iter()
+            .map(|entry| Tool {
+                info: entry.info,
+                page: (entry.create)(cc),
+            })
+            .collect();
+
+        let mut dock = cc
+            .storage
+            .and_then(|storage| eframe::get_value::<DockState<Tab>>(storage, DOCK_LAYOUT_KEY))
+            .unwrap_or_else(|| DockState::new(vec![Tab::Home]));
+        // 去掉这次没有编译进来的工具
+        dock.retain_tabs(|tab| match tab {
+            Tab::Home | Tab::History | Tab::Console => true,
+            Tab::Tool(id) => tools.iter().any(|tool| tool.info.id == id),
+        });
+        if dock.find_tab(&Tab::Home).is_none() {
+            dock = DockState::new(vec![Tab::Home]);
+        }
+
         Self {
-            interfaces: {
-                let mut interfaces: HashMap<ActiveInterface, Box<dyn Interface>> = HashMap::new();
-                interfaces.insert(ActiveInterface::LogicTool, Box::new(LogicToolPage::new(cc)));
-                interfaces.insert(
-                    ActiveInterface::HardfaultTool,
-                    Box::new(HardfaultToolPage::new(cc)),
-                );
-                interfaces.insert(ActiveInterface::HciTool, Box::new(HciToolPage::new(cc)));
-                interfaces.insert(
-                    ActiveInterface::AnalyzeTool,
-                    Box::new(AnalyzeToolPage::new(cc)),
-                );
-                interfaces
+            tools,
+            dock,
+            history: load_settings(cc.storage, HISTORY_KEY),
+            console: Console::default(),
+            workspace: WorkspaceState {
+                path: cc
+                    .storage
+                    .and_then(|storage| eframe::get_value(storage, WORKSPACE_PATH_KEY))
+                    .unwrap_or_default(),
+                status: None,
             },
-            current_page: ActiveInterface::Home,
+            font,
         }
     }
 
-    fn show_main_page(&mut self, ui: &mut Ui) {
-        // 上半部分：文字描述
-        ui.heading("Home");
-        ui.label(format!("编译时间：{}", COMPILE_TIME));
-        ui.label(format!("git 信息：{} ({})", &GIT_HASH[0..8], GIT_TIMESTAMP));
-        ui.label("");
-
-        ui.label("在下方选择对应的功能");
-        ui.label("需要处理的文件可以直接拖入对应窗口\n");
-
-        ui.label("输入文件编码的说明：");
-        ui.label("utf8 不会转化，other 会自己猜测编码");
-        ui.label("建议自己选择编码格式，猜测的编码可能会不对");
-        ui.label("hci tool 的话，存在中文字符就用other，避免转完之后还有中文；log2cfa.exe 不支持中文字符");
-        ui.label("转换完后，会有 .bak 文件作为备份");
-        ui.separator();
-
-        // 下半部分：应用宫格排列
-        ScrollArea::vertical().show(ui, |ui| {
-            let line_size = 3;
-            let total = self.interfaces.len();
-            let line = (total + line_size - 1) / line_size;
-            let mut cnt = 0;
-
-            for l in 0..line {
-                ui.columns(3, |columns| {
-                    for (i, column) in columns.iter_mut().enumerate() {
-                        cnt += 1;
-                        if cnt > total {
-                            break;
-                        }
-
-                        let page =
-                            ActiveInterface::try_from((i + l * line_size + 1) as u32).unwrap();
-                        let app_name = format!("{}", page.as_str());
-
-                        if column
-                            .add_sized(vec2(100.0, 100.0), egui::Button::new(&app_name))
-                            .clicked()
-                        {
-                            self.current_page = page;
-                        }
-                    }
-                });
+    fn is_open(&self, tool: &Tool) -> bool {
+        self.dock
+            .find_tab(&Tab::Tool(tool.info.id.to_string()))
+            .is_some()
+    }
+
+    /// 保存所有打开的工具的输入和选项
+    fn save_workspace(&self, path: &Path) -> std::io::Result<String> {
+        let mut workspace = Workspace::default();
+        for tool in self.tools.iter().filter(|tool| self.is_open(tool)) {
+            workspace.push(tool.info.id, tool.page.inputs(), &tool.page.options());
+        }
+        workspace.save(path)?;
+        Ok(tr!("workspace.saved", count = workspace.tools.len()))
+    }
+
+    /// 打开工作区里的工具并恢复输入和选项，没有编译进来的工具跳过，
+    /// 选项不对的工具保持原样，其余照常恢复，最后一起报错
+    fn open_workspace(&mut self, path: &Path) -> std::io::Result<String> {
+        let workspace = Workspace::load(path)?;
+        let mut skipped = Vec::new();
+        let mut failed = Vec::new();
+        for entry in workspace.tools {
+            let Some(tool) = self
+                .tools
+                .iter_mut()
+                .find(|tool| tool.info.id == entry.tool)
+            else {
+                skipped.push(entry.tool);
+                continue;
+            };
+            if let Err(e) = tool.page.apply(entry.inputs, &entry.options.to_string()) {
+                failed.push(format!("{}: {}", entry.tool, e));
+                continue;
+            }
+            self.open_tab(Tab::Tool(entry.tool));
+        }
+        if !failed.is_empty() {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                tr!("workspace.failed", tools = failed.join("; ")),
+            ))
+        } else if skipped.is_empty() {
+            Ok(tr!("workspace.opened").to_string())
+        } else {
+            Ok(tr!("workspace.unsupported", tools = skipped.join(", ")))
+        }
+    }
+
+    /// 所有打开的工具都处理一遍自己列表里的文件
+    fn run_all(&mut self, ctx: &egui::Context) {
+        let open: Vec<bool> = self.tools.iter().map(|tool| self.is_open(tool)).collect();
+        for (tool, open) in self.tools.iter_mut().zip(open) {
+            if open && !tool.page.inputs().is_empty() {
+                tool.page.run(ctx);
+            }
+        }
+    }
+
+    fn handle_home_action(&mut self, ctx: &egui::Context, action: HomeAction) {
+        let path = std::path::PathBuf::from(&self.workspace.path);
+        let status = match action {
+            HomeAction::Open(tab) => {
+                self.open_tab(tab);
+                return;
+            }
+            HomeAction::RunAll => {
+                self.run_all(ctx);
+                return;
+            }
+            HomeAction::SaveWorkspace => self.save_workspace(&path),
+            HomeAction::OpenWorkspace => self.open_workspace(&path),
+        };
+        if let Err(e) = &status {
+            log::error!("workspace {}: {}", path.display(), e);
+        }
+        self.workspace.status = Some(status.map_err(|e| e.to_string()));
+    }
+
+    /// 已经打开的标签页切到前台，否则在当前区域新开一个
+    fn open_tab(&mut self, tab: Tab) {
+        if let Some((surface, node, tab_index)) = self.dock.find_tab(&tab) {
+            self.dock.set_active_tab((surface, node, tab_index));
+            self.dock.set_focused_node_and_surface((surface, node));
+        } else {
+            self.dock.push_to_focused_leaf(tab);
+        }
+    }
+}
+
+/// 首页，返回点击的操作
+fn show_main_page(
+    ui: &mut Ui,
+    tools: &[Tool],
+    workspace: &mut WorkspaceState,
+    font: &mut FontSettings,
+) -> Option<HomeAction> {
+    let mut clicked = None;
+
+    // 上半部分：文字描述
+    ui.horizontal(|ui| {
+        ui.heading(tr!("home.title"));
+        if ui.button(tr!("history.title")).clicked() {
+            clicked = Some(HomeAction::Open(Tab::History));
+        }
+        if ui.button(tr!("console.title")).clicked() {
+            clicked = Some(HomeAction::Open(Tab::Console));
+        }
+        language_select(ui);
+    });
+    ui.label(tr!("home.compile_time", time = COMPILE_TIME));
+    ui.label(tr!(
+        "home.git",
+        hash = &GIT_HASH[0..8],
+        time = GIT_TIMESTAMP
+    ));
+    ui.label("");
+
+    ui.label(tr!("home.help.tabs"));
+    ui.label(tr!("home.help.drop"));
+
+    ui.label(tr!("home.help.encoding"));
+    ui.label(tr!("home.help.encoding_other"));
+    ui.label(tr!("home.help.encoding_choose"));
+    ui.label(tr!("home.help.hci"));
+    ui.label(tr!("home.help.untouched"));
+    ui.separator();
+
+    ui.horizontal(|ui| {
+        ui.label(tr!("home.workspace"));
+        path_edit(
+            ui,
+            &mut workspace.path,
+            "workspace.json",
+            Browse::Save(&["json"]),
+        );
+        ui.add_enabled_ui(!workspace.path.is_empty(), |ui| {
+            if ui.button(tr!("common.save")).clicked() {
+                clicked = Some(HomeAction::SaveWorkspace);
+            }
+            if ui.button(tr!("common.open")).clicked() {
+                clicked = Some(HomeAction::OpenWorkspace);
             }
         });
+        if ui.button(tr!("home.run_all")).clicked() {
+            clicked = Some(HomeAction::RunAll);
+        }
+    });
+    match &workspace.status {
+        Some(Ok(msg)) => {
+            ui.label(msg);
+        }
+        Some(Err(e)) => {
+            ui.colored_label(ui.visuals().error_fg_color, e);
+        }
+        None => {}
+    }
+    ui.separator();
+
+    font.ui(ui);
+    ui.separator();
+
+    // 下半部分：应用宫格排列
+    ScrollArea::vertical().show(ui, |ui| {
+        let line_size = 3;
+        for line in tools.chunks(line_size) {
+            ui.columns(line_size, |columns| {
+                for (column, tool) in columns.iter_mut().zip(line) {
+                    let text = format!("{}\n{}", tool.info.icon, tr!(tool.info.name));
+                    if column
+                        .add_sized(vec2(100.0, 100.0), egui::Button::new(text))
+                        .on_hover_text(tr!(tool.info.description))
+                        .clicked()
+                    {
+                        clicked = Some(HomeAction::Open(Tab::Tool(tool.info.id.to_string())));
+                    }
+                }
+            });
+        }
+    });
+
+    ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
+        ui.hyperlink("https://github.com/greedyhao/worktool");
+        egui::warn_if_debug_build(ui);
+    });
+
+    clicked
+}
 
-        ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
-            ui.hyperlink("https://github.com/greedyhao/worktool");
-            egui::warn_if_debug_build(ui);
+fn language_select(ui: &mut Ui) {
+    let mut language = i18n::language();
+    egui::ComboBox::from_label(tr!("home.language"))
+        .selected_text(language.name())
+        .show_ui(ui, |ui| {
+            for option in Language::ALL {
+                ui.selectable_value(&mut language, option, option.name());
+            }
         });
+    if language != i18n::language() {
+        i18n::set_language(language);
+    }
+}
+
+/// 显示标签页的内容，打开和关闭标签页在 `DockArea` 显示完之后处理
+struct ToolViewer<'a> {
+    tools: &'a mut [Tool],
+    history: &'a mut History,
+    console: &'a mut Console,
+    workspace: &'a mut WorkspaceState,
+    font: &'a mut FontSettings,
+    action: Option<HomeAction>,
+    closed: Vec<Tab>,
+    rerun: Option<RunRecord>,
+}
+
+impl TabViewer for ToolViewer<'_> {
+    type Tab = Tab;
+
+    fn title(&mut self, tab: &mut Tab) -> WidgetText {
+        match tab {
+            Tab::Home => tr!("home.tab").into(),
+            Tab::History => tr!("history.title").into(),
+            Tab::Console => tr!("console.title").into(),
+            Tab::Tool(id) => match self.tools.iter().find(|tool| tool.info.id == id) {
+                Some(tool) => format!("{} {}", tool.info.icon, tr!(tool.info.name)).into(),
+                None => id.as_str().into(),
+            },
+        }
+    }
+
+    fn id(&mut self, tab: &mut Tab) -> egui::Id {
+        egui::Id::new(&*tab)
+    }
+
+    fn ui(&mut self, ui: &mut Ui, tab: &mut Tab) {
+        match tab {
+            Tab::Home => {
+                if let Some(action) = show_main_page(ui, self.tools, self.workspace, self.font) {
+                    self.action = Some(action);
+                }
+            }
+            Tab::History => {
+                let tools: Vec<ToolInfo> = self.tools.iter().map(|tool| tool.info).collect();
+                if let Some(run) = self.history.ui(ui, &tools) {
+                    self.rerun = Some(run);
+                }
+            }
+            Tab::Console => self.console.ui(ui),
+            Tab::Tool(id) => {
+                let ctx = ui.ctx().clone();
+                let closed = &mut self.closed;
+                if let Some(tool) = self.tools.iter_mut().find(|tool| tool.info.id == id) {
+                    let tab = tab.clone();
+                    tool.page
+                        .new_update(ui, &ctx, Box::new(move || closed.push(tab.clone())));
+                }
+            }
+        }
+    }
+
+    fn closeable(&mut self, tab: &mut Tab) -> bool {
+        *tab != Tab::Home
     }
 }
 
 impl eframe::App for WorkToolApp {
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        for (_, interface) in self.interfaces.iter_mut() {
-            interface.save(storage);
+        for tool in self.tools.iter_mut() {
+            tool.page.save(storage);
         }
+        eframe::set_value(storage, DOCK_LAYOUT_KEY, &self.dock);
+        eframe::set_value(storage, HISTORY_KEY, &self.history);
+        eframe::set_value(storage, WORKSPACE_PATH_KEY, &self.workspace.path);
+        eframe::set_value(storage, LANGUAGE_KEY, &i18n::language());
+        eframe::set_value(storage, FONT_KEY, &self.font.path);
     }
 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        egui::CentralPanel::default().show(ctx, |ui| {
-            if self.current_page == ActiveInterface::Home {
-                self.show_main_page(ui);
-            } else {
-                if let Some(interface) = self.interfaces.get_mut(&self.current_page) {
-                    interface.new_update(
-                        ui,
-                        ctx,
-                        Box::new(|| self.current_page = ActiveInterface::Home),
-                    );
-                }
+        // 切到别的标签页或者首页时，任务和监控文件夹也要继续处理
+        for tool in self.tools.iter_mut() {
+            tool.page.tick(ctx);
+        }
+
+        let mut viewer = ToolViewer {
+            tools: &mut self.tools,
+            history: &mut self.history,
+            console: &mut self.console,
+            workspace: &mut self.workspace,
+            font: &mut self.font,
+            action: None,
+            closed: Vec::new(),
+            rerun: None,
+        };
+        DockArea::new(&mut self.dock)
+            .style(Style::from_egui(ctx.style().as_ref()))
+            .show(ctx, &mut viewer);
+
+        let ToolViewer {
+            action,
+            closed,
+            rerun,
+            ..
+        } = viewer;
+        for tab in closed {
+            if let Some(index) = self.dock.find_tab(&tab) {
+                self.dock.remove_tab(index);
             }
-        });
-    }
-}
+        }
+        if let Some(action) = action {
+            self.handle_home_action(ctx, action);
+        }
+        if let Some(run) = rerun {
+            if let Some(tool) = self.tools.iter_mut().find(|tool| tool.info.id == run.tool) {
+                let inputs = if run.roots.is_empty() {
+                    run.inputs
+                } else {
+                    run.roots
+                };
+                tool.page.rerun(ctx, inputs, &run.options);
+                self.open_tab(Tab::Tool(run.tool));
+            }
+        }
 
-fn setup_custom_fonts(ctx: &egui::Context) {
-    // Start with the default fonts (we will be adding to them rather than replacing them).
-    let mut fonts = egui::FontDefinitions::default();
-
-    // Install my own font (maybe supporting non-latin characters).
-    // .ttf and .otf files supported.
-    #[cfg(target_os = "windows")]
-    fonts.font_data.insert(
-        "my_font".to_owned(),
-        Arc::new(egui::FontData::from_static(include_bytes!(
-            "c:/Windows/Fonts/msyh.ttc"
-        ))),
-    );
-
-    // Put my font first (highest priority) for proportional text:
-    fonts
-        .families
-        .entry(egui::FontFamily::Proportional)
-        .or_default()
-        .insert(0, "my_font".to_owned());
-
-    // Put my font as last fallback for monospace:
-    fonts
-        .families
-        .entry(egui::FontFamily::Monospace)
-        .or_default()
-        .push("my_font".to_owned());
-
-    // Tell egui to use these fonts:
-    ctx.set_fonts(fonts);
+        for tool in self.tools.iter_mut() {
+            if let Some(run) = tool.page.take_run() {
+                self.history.push(tool.info.id, run);
+            }
+        }
+    }
 }
@@ -0,0 +1,488 @@
+//! 命令行入口，每个工具页面对应一个子命令
+//!
+//! ```text
+//! worktool logic spi --format bluetrum capture.csv
+//! worktool hardfault crash.log --json
+//! worktool convert logs -r --include "*.log" --to utf8 --eol lf --dry-run
+//! ```
+
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+#[cfg(feature = "analyze")]
+use crate::core::analyze;
+#[cfg(feature = "convert")]
+use crate::core::convert::{self, ConvertOptions, LineEnding, TargetEncoding};
+use crate::core::encoding::FileEncoding;
+#[cfg(feature = "hardfault")]
+use crate::core::hardfault;
+#[cfg(feature = "hci")]
+use crate::core::hci;
+#[cfg(feature = "logic")]
+use crate::core::logic::{self, LogicIISArgs, LogicSpiArgs, LogicUARTArgs, SpiConvType};
+use crate::core::output::DEFAULT_TEMPLATE;
+use crate::core::{Conflict, Error, OutputPolicy, Progress};
+
+/// 处理失败时的退出码，参数错误由 clap 返回 2
+const EXIT_FAILURE: i32 = 1;
+
+#[derive(Parser, Debug)]
+#[command(name = "worktool", version, about = "worktool command line interface")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+#[derive(Args, Debug)]
+struct OutputArgs {
+    /// Directory for output files, defaults to the input file's directory
+    #[arg(long, global = true)]
+    output_dir: Option<PathBuf>,
+    /// Output file name template, supports {name}, {stem}, {format} and {timestamp}
+    #[arg(long, global = true, default_value = DEFAULT_TEMPLATE)]
+    name: String,
+    /// What to do when an output file already exists
+    #[arg(long, global = true, value_enum, default_value_t = OnConflict::Overwrite)]
+    on_conflict: OnConflict,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum OnConflict {
+    Overwrite,
+    /// Leave the existing file and skip the input
+    Skip,
+    /// Append `_1`, `_2` ... to the new file name
+    Suffix,
+}
+
+impl From<OutputArgs> for OutputPolicy {
+    fn from(args: OutputArgs) -> Self {
+        OutputPolicy {
+            dir: args.output_dir,
+            template: args.name,
+            conflict: match args.on_conflict {
+                OnConflict::Overwrite => Conflict::Overwrite,
+                OnConflict::Skip => Conflict::Skip,
+                OnConflict::Suffix => Conflict::Suffix,
+            },
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Convert Kingst logic analyzer CSV exports
+    #[cfg(feature = "logic")]
+    Logic {
+        #[command(subcommand)]
+        protocol: LogicCommand,
+    },
+    /// Parse hardfault / watchdog register dumps
+    #[cfg(feature = "hardfault")]
+    Hardfault {
+        file: PathBuf,
+        #[command(flatten)]
+        encoding: EncodingArg,
+        /// Only print the register group with this index
+        #[arg(long)]
+        group: Option<usize>,
+        /// Print register groups as JSON
+        #[arg(long)]
+        json: bool,
+        /// Also write the register groups to the `regs.txt` output
+        #[arg(long)]
+        report: bool,
+    },
+    /// Convert an HCI text log to a btsnoop file (HCI UART H4)
+    #[cfg(feature = "hci")]
+    Hci {
+        file: PathBuf,
+        #[command(flatten)]
+        encoding: EncodingArg,
+    },
+    /// Extract typed messages from an SPI log capture
+    #[cfg(feature = "analyze")]
+    Analyze {
+        file: PathBuf,
+        /// Comma separated message types to keep, e.g. "A,B"
+        #[arg(long)]
+        types: String,
+        /// Output file, defaults to the `out.txt` output of the output policy
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Convert the encoding and line endings of text files
+    #[cfg(feature = "convert")]
+    Convert {
+        /// Files or folders, folders are searched for files matching `--include`
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+        /// Input encoding, `other` guesses it for every file.
+        /// A byte order mark at the start of the file takes precedence
+        #[arg(long, value_enum, default_value_t = EncodingName::Other)]
+        from: EncodingName,
+        /// Output encoding, UTF-16 and UTF-32 outputs start with a byte order mark
+        #[arg(long, value_enum, default_value_t = TargetName::Utf8)]
+        to: TargetName,
+        /// Output line endings
+        #[arg(long, value_enum, default_value_t = EolName::Keep)]
+        eol: EolName,
+        /// Also search subfolders
+        #[arg(short, long)]
+        recursive: bool,
+        /// File name patterns for folders, e.g. "*.log *.txt", empty matches every file
+        #[arg(long, default_value = "")]
+        include: String,
+        /// Only report what would be converted, write nothing
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[cfg(feature = "convert")]
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum TargetName {
+    Utf8,
+    /// UTF-8 with a byte order mark
+    #[value(name = "utf8-bom")]
+    Utf8Bom,
+    #[value(name = "utf16le", alias = "utf16")]
+    Utf16Le,
+    #[value(name = "utf16be")]
+    Utf16Be,
+    #[value(name = "utf32le", alias = "utf32")]
+    Utf32Le,
+    #[value(name = "utf32be")]
+    Utf32Be,
+    Gbk,
+    Gb18030,
+    Big5,
+    #[value(name = "shift-jis", alias = "sjis")]
+    ShiftJis,
+}
+
+#[cfg(feature = "convert")]
+impl From<TargetName> for TargetEncoding {
+    fn from(name: TargetName) -> Self {
+        match name {
+            TargetName::Utf8 => TargetEncoding::UTF8,
+            TargetName::Utf8Bom => TargetEncoding::UTF8Bom,
+            TargetName::Utf16Le => TargetEncoding::UTF16LE,
+            TargetName::Utf16Be => TargetEncoding::UTF16BE,
+            TargetName::Utf32Le => TargetEncoding::UTF32LE,
+            TargetName::Utf32Be => TargetEncoding::UTF32BE,
+            TargetName::Gbk => TargetEncoding::GBK,
+            TargetName::Gb18030 => TargetEncoding::GB18030,
+            TargetName::Big5 => TargetEncoding::Big5,
+            TargetName::ShiftJis => TargetEncoding::ShiftJIS,
+        }
+    }
+}
+
+#[cfg(feature = "convert")]
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum EolName {
+    /// Keep the line endings of the input
+    Keep,
+    Lf,
+    Crlf,
+}
+
+#[cfg(feature = "convert")]
+impl From<EolName> for LineEnding {
+    fn from(name: EolName) -> Self {
+        match name {
+            EolName::Keep => LineEnding::Keep,
+            EolName::Lf => LineEnding::Lf,
+            EolName::Crlf => LineEnding::CrLf,
+        }
+    }
+}
+
+#[cfg(feature = "logic")]
+#[derive(Subcommand, Debug)]
+enum LogicCommand {
+    /// SPI export, `Time [s],Packet ID,MOSI,MISO`
+    Spi {
+        file: PathBuf,
+        #[arg(long, value_enum, default_value_t = SpiFormat::Raw)]
+        format: SpiFormat,
+    },
+    /// IIS export, `Time [s],Channel,Value`
+    Iis { file: PathBuf },
+    /// UART export, `Time [s],Value,Parity Error,Framing Error`
+    Uart { file: PathBuf },
+}
+
+#[cfg(feature = "logic")]
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum SpiFormat {
+    /// Raw bytes, written to `<file>.out`
+    Raw,
+    /// Bluetrum voice dump, written to `<file>.out1/.out2/.out3`
+    Bluetrum,
+    /// Hex text, written to `<file>.txt`
+    Txt,
+}
+
+#[cfg(feature = "logic")]
+impl From<SpiFormat> for SpiConvType {
+    fn from(format: SpiFormat) -> Self {
+        match format {
+            SpiFormat::Raw => SpiConvType::RAW,
+            SpiFormat::Bluetrum => SpiConvType::BluetrumVoiceDump,
+            SpiFormat::Txt => SpiConvType::TXT,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+struct EncodingArg {
+    /// Input file encoding, the input is decoded while reading and never modified.
+    /// A byte order mark at the start of the file takes precedence
+    #[arg(long, value_enum, default_value_t = EncodingName::Utf8)]
+    encoding: EncodingName,
+    /// Also save the decoded input as the `utf8` output
+    #[arg(long)]
+    save_normalized: bool,
+}
+
+#[cfg(any(feature = "hardfault", feature = "hci"))]
+impl EncodingArg {
+    /// `also` 是这次处理还会写出的另一种输出格式，两个输出不能同名
+    fn save_normalized(
+        &self,
+        file: &std::path::Path,
+        output: &OutputPolicy,
+        progress: &Progress,
+        also: Option<&str>,
+    ) -> Result<(), Error> {
+        if self.save_normalized {
+            if let Some(format) = also {
+                output.check_formats(&[crate::core::encoding::NORMALIZED_FORMAT, format])?;
+            }
+            let out = crate::core::encoding::save_normalized(
+                file,
+                &self.encoding.into(),
+                output,
+                progress,
+            )?;
+            eprintln!("utf8 copy written to {}", out.display());
+        }
+        Ok(())
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum EncodingName {
+    Utf8,
+    #[value(name = "utf16le", alias = "utf16")]
+    Utf16Le,
+    #[value(name = "utf16be")]
+    Utf16Be,
+    #[value(name = "utf32le", alias = "utf32")]
+    Utf32Le,
+    #[value(name = "utf32be")]
+    Utf32Be,
+    Gbk,
+    Gb18030,
+    Big5,
+    #[value(name = "shift-jis", alias = "sjis")]
+    ShiftJis,
+    /// Guess the encoding
+    Other,
+}
+
+impl From<EncodingName> for FileEncoding {
+    fn from(name: EncodingName) -> Self {
+        match name {
+            EncodingName::Utf8 => FileEncoding::UTF8,
+            EncodingName::Utf16Le => FileEncoding::UTF16LE,
+            EncodingName::Utf16Be => FileEncoding::UTF16BE,
+            EncodingName::Utf32Le => FileEncoding::UTF32LE,
+            EncodingName::Utf32Be => FileEncoding::UTF32BE,
+            EncodingName::Gbk => FileEncoding::GBK,
+            EncodingName::Gb18030 => FileEncoding::GB18030,
+            EncodingName::Big5 => FileEncoding::Big5,
+            EncodingName::ShiftJis => FileEncoding::ShiftJIS,
+            EncodingName::Other => FileEncoding::Other,
+        }
+    }
+}
+
+/// 解析参数并执行对应的工具，返回进程退出码
+pub fn run<I, T>(args: I) -> i32
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let cli = match Cli::try_parse_from(args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            e.print().ok();
+            return e.exit_code();
+        }
+    };
+
+    let ret = match execute(cli.command, &cli.output.into()) {
+        Ok(()) => 0,
+        Err(e) => match e.downcast_ref::<Error>() {
+            // 跳过已存在的输出不算失败
+            Some(Error::Skipped(path)) => {
+                eprintln!("skipped: {} already exists", path.display());
+                0
+            }
+            _ => {
+                eprintln!("error: {}", e);
+                EXIT_FAILURE
+            }
+        },
+    };
+    std::io::stdout().flush().ok();
+    ret
+}
+
+// 一个工具都不编译时没有子命令
+#[cfg_attr(not(any_tool), allow(unused_variables, unreachable_code))]
+fn execute(command: Command, output: &OutputPolicy) -> Result<(), Box<dyn std::error::Error>> {
+    let progress = Progress::default();
+    match command {
+        #[cfg(feature = "logic")]
+        Command::Logic { protocol } => {
+            let outputs = match protocol {
+                LogicCommand::Spi { file, format } => {
+                    let args = LogicSpiArgs {
+                        conv_type: format.into(),
+                    };
+                    logic::spi_file(&args, &file, output, &progress)?
+                }
+                LogicCommand::Iis { file } => {
+                    logic::iis_file(&LogicIISArgs::default(), &file, output, &progress)?
+                }
+                LogicCommand::Uart { file } => {
+                    logic::uart_file(&LogicUARTArgs::default(), &file, output, &progress)?
+                }
+            };
+            for output in outputs {
+                println!("{}", output.display());
+            }
+        }
+        #[cfg(feature = "hardfault")]
+        Command::Hardfault {
+            file,
+            encoding,
+            group,
+            json,
+            report,
+        } => {
+            let also = report.then_some(hardfault::REPORT_FORMAT);
+            encoding.save_normalized(&file, output, &progress, also)?;
+            let mut regs = hardfault::parse_file(&file, &encoding.encoding.into(), &progress)?;
+            if let Some(group) = group {
+                if group >= regs.len() {
+                    return Err(format!(
+                        "register group {} out of range, {} found",
+                        group,
+                        regs.len()
+                    )
+                    .into());
+                }
+                regs = vec![regs.swap_remove(group)];
+            }
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&regs)?);
+            } else {
+                for reg in &regs {
+                    println!("{}\n", reg.display());
+                }
+            }
+            if report {
+                if let Some(out) = hardfault::report_file(&file, &regs, output)? {
+                    eprintln!("report written to {}", out.display());
+                }
+            }
+        }
+        #[cfg(feature = "hci")]
+        Command::Hci { file, encoding } => {
+            encoding.save_normalized(&file, output, &progress, Some(hci::BTSNOOP_FORMAT))?;
+            let out = hci::convert_file(&file, &encoding.encoding.into(), output, &progress)?;
+            println!("{}", out.display());
+        }
+        #[cfg(feature = "analyze")]
+        Command::Analyze {
+            file,
+            types,
+            output: out,
+        } => {
+            let out = match out {
+                Some(out) => out,
+                None => output.resolve(&file, analyze::OUTPUT_FORMAT)?,
+            };
+            let cnt = analyze::analyze_file(&types, &file, &out, &progress)?;
+            println!("{} messages written to {}", cnt, out.display());
+        }
+        #[cfg(feature = "convert")]
+        Command::Convert {
+            inputs,
+            from,
+            to,
+            eol,
+            recursive,
+            include,
+            dry_run,
+        } => {
+            let options = ConvertOptions {
+                source: from.into(),
+                target: to.into(),
+                line_ending: eol.into(),
+            };
+            let files = convert::collect(&inputs, recursive, &include);
+            if files.is_empty() {
+                return Err("no input files found".into());
+            }
+            let mut failed = 0;
+            for file in &files {
+                let policy = convert::mirror(output, &inputs, file);
+                match convert::convert_file(file, &options, &policy, dry_run, &progress) {
+                    Ok(report) => {
+                        let stats = report.stats;
+                        println!(
+                            "{}: {} -> {}, crlf {} lf {} cr {}, {} decode error(s), {} unmappable, {} {}",
+                            file.display(),
+                            report.source.name(),
+                            options.target.name(),
+                            stats.crlf,
+                            stats.lf,
+                            stats.cr,
+                            report.decode_error_count,
+                            stats.unmappable,
+                            if report.written { "wrote" } else { "would write" },
+                            report.output.display()
+                        );
+                    }
+                    Err(Error::Skipped(path)) => {
+                        println!(
+                            "{}: skipped, {} already exists",
+                            file.display(),
+                            path.display()
+                        );
+                    }
+                    Err(e) => {
+                        eprintln!("{}: {}", file.display(), e);
+                        failed += 1;
+                    }
+                }
+            }
+            if failed > 0 {
+                return Err(format!("{} of {} files failed", failed, files.len()).into());
+            }
+        }
+    }
+    Ok(())
+}
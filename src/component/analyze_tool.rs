@@ -1,57 +1,89 @@
 use crate::add_drop_file;
 use crate::component::preview_files_being_dropped;
 use crate::component::show_page_header;
-use crate::component::Interface;
-
-use std::collections::HashSet;
-use std::error::Error;
-use std::io::{self, BufRead, BufReader, BufWriter, Write};
-use std::sync::mpsc;
-use std::{
-    fs::File,
-    sync::mpsc::{Receiver, Sender},
-    thread,
+use crate::component::{
+    load_settings, output_policy_ui, BatchResult, FileQueue, FinishedRun, FolderWatch, Interface,
+    JobRunner, Settings, ToolInfo,
 };
+use crate::core::{analyze, OutputPolicy};
+use crate::tr;
+
+use std::path::PathBuf;
 
 static ANALYZE_TOOL_PAGE_KEY: &str = "AnalyzeKey";
 
 #[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 struct ToolSave {
+    version: u32,
+    types: String,
+    output: OutputPolicy,
+    /// 上次列表里的文件
+    inputs: Vec<PathBuf>,
+    recent: Vec<PathBuf>,
+    watch_dir: String,
+    watching: bool,
+}
+
+/// 历史记录里保存的处理选项
+#[derive(serde::Deserialize, serde::Serialize)]
+struct RunOptions {
     types: String,
+    output: OutputPolicy,
+}
+
+impl Settings for ToolSave {
+    /// 1: 保存文件列表，0 只有 `types`
+    const VERSION: u32 = 1;
+
+    fn version(&mut self) -> &mut u32 {
+        &mut self.version
+    }
 }
 
 pub struct AnalyzeToolPage {
     save: ToolSave,
-    path: String,
-    history: Option<String>,
-    channel: (Sender<bool>, Receiver<bool>),
-    doing: bool,
+    files: FileQueue,
+    history: Option<Vec<PathBuf>>,
+    job: JobRunner<BatchResult<Vec<PathBuf>>>,
+    watch: FolderWatch,
 }
 
 add_drop_file!(AnalyzeToolPage);
 
 impl eframe::App for AnalyzeToolPage {
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.save.inputs = self.files.paths();
+        self.save.recent = self.files.recent().to_vec();
         eframe::set_value(storage, ANALYZE_TOOL_PAGE_KEY, &self.save);
     }
     fn update(&mut self, _ctx: &egui::Context, _frame: &mut eframe::Frame) {}
 }
 
 impl Interface for AnalyzeToolPage {
+    fn info() -> ToolInfo {
+        ToolInfo {
+            id: "analyze",
+            name: "analyze.name",
+            description: "analyze.description",
+            icon: "🔍",
+        }
+    }
     fn new(cc: &eframe::CreationContext<'_>) -> Self
     where
         Self: Sized,
     {
         let mut page = AnalyzeToolPage {
-            save: ToolSave::default(),
-            path: String::new(),
+            save: load_settings(cc.storage, ANALYZE_TOOL_PAGE_KEY),
+            files: FileQueue::new(&["csv"]),
             history: None,
-            doing: false,
-            channel: mpsc::channel(),
+            job: JobRunner::default(),
+            watch: FolderWatch::new(&["csv"], &[analyze::OUTPUT_FORMAT]),
         };
 
-        if let Some(storage) = cc.storage {
-            page.save = eframe::get_value(storage, ANALYZE_TOOL_PAGE_KEY).unwrap_or_default();
+        page.files.restore(&page.save.inputs, &page.save.recent);
+        if page.save.watching {
+            page.watch.start(&cc.egui_ctx, &page.save.watch_dir);
         }
         page
     }
@@ -63,143 +95,90 @@ impl Interface for AnalyzeToolPage {
     ) {
         show_page_header(ui, close);
 
-        egui::Grid::new("hci")
+        egui::Grid::new("analyze")
             .num_columns(2)
             .spacing([40.0, 4.0])
             .striped(true)
             .show(ui, |ui| self.grid_contents(ctx, ui));
 
-        if let Ok(_status) = self.channel.1.try_recv() {
-            self.doing = false;
-        }
+        self.job.status_bar(ui);
+
         self.get_drop_file(ctx, ui);
     }
-}
-
-impl AnalyzeToolPage {
-    fn grid_contents(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) {
-        ui.label("文件地址");
-        ui.text_edit_singleline(&mut self.path);
-        ui.end_row();
 
-        ui.label("需要转换的类型");
-        ui.text_edit_singleline(&mut self.save.types);
-        ui.end_row();
-
-        ui.add_enabled_ui(!self.doing && self.path.len() > 0, |ui| {
-            if ui.button("处理").clicked() {
-                self.doing = true;
-                let tx = self.channel.0.clone();
-                let types = self.save.types.clone();
-                let input_path = self.path.clone();
-                let output_path = format!("{}.out.txt", input_path);
-
-                thread::spawn(move || {
-                    analyze_main(&types, &input_path, &output_path);
-                    tx.send(true).unwrap();
-                });
-            }
-        });
+    fn tick(&mut self, ctx: &egui::Context) {
+        if let Some(Ok(batch)) = self.job.poll() {
+            self.files.set_results(&batch);
+        }
+        if let Some(paths) = self.watch.poll(&mut self.files, self.job.is_running()) {
+            self.start(ctx, paths);
+        }
     }
-}
 
-#[derive(Debug)]
-struct LogMessage {
-    timestamp: f64,
-    content: String,
-}
-
-fn process_logic_data(reader: impl BufRead) -> Result<Vec<LogMessage>, Box<dyn Error>> {
-    let mut messages = Vec::new();
-    let mut current_message = String::with_capacity(100); // 预分配内存
-    let mut start_time: Option<f64> = None;
-
-    let mut csv_reader = csv::ReaderBuilder::new()
-        .has_headers(true)
-        .flexible(true)
-        .from_reader(reader);
-
-    // 获取并缓存列索引
-    let headers = csv_reader.headers()?;
-    let time_idx = headers
-        .iter()
-        .position(|h| h == "Time [s]")
-        .ok_or("Missing 'Time [s]' column")?;
-    let mosi_idx = headers
-        .iter()
-        .position(|h| h == "MOSI")
-        .ok_or("Missing 'MOSI' column")?;
-
-    // 使用 into_records 避免克隆
-    for result in csv_reader.into_records() {
-        let record = result?;
-
-        let time: f64 = record.get(time_idx).ok_or("Missing time field")?.parse()?;
-
-        let char = record.get(mosi_idx).ok_or("Missing MOSI field")?;
-
-        if start_time.is_none() {
-            start_time = Some(time);
-        }
+    fn take_run(&mut self) -> Option<FinishedRun> {
+        self.job.take_run()
+    }
 
-        match char {
-            "NUL" => current_message.push(' '),
-            "LF " => {
-                if !current_message.is_empty() {
-                    let message = current_message.trim().to_string();
-                    if message.contains(':') {
-                        if let Some(timestamp) = start_time {
-                            messages.push(LogMessage {
-                                timestamp,
-                                content: message,
-                            });
-                        }
-                        start_time = None;
-                    }
-                }
-                current_message.clear();
-            }
-            c => current_message.push_str(c),
-        }
+    fn inputs(&self) -> Vec<PathBuf> {
+        self.files.paths()
     }
 
-    // let len = messages.len();
+    fn options(&self) -> String {
+        serde_json::to_string(&RunOptions {
+            types: self.save.types.clone(),
+            output: self.save.output.clone(),
+        })
+        .unwrap_or_default()
+    }
 
-    Ok(messages)
-}
+    fn apply(&mut self, inputs: Vec<PathBuf>, options: &str) -> serde_json::Result<()> {
+        let options: RunOptions = serde_json::from_str(options)?;
+        self.save.types = options.types;
+        self.save.output = options.output;
+        self.files.replace(inputs);
+        Ok(())
+    }
 
-#[inline]
-fn validate_message_type(message: &str, map: &HashSet<String>) -> bool {
-    if let Some(msg_type) = message.split(':').next() {
-        map.contains(msg_type)
-    } else {
-        false
+    fn run(&mut self, ctx: &egui::Context) {
+        self.start(ctx, self.files.paths());
     }
 }
 
-fn write_output(messages: &[LogMessage], output_file: &str) -> io::Result<()> {
-    let file = File::create(output_file)?;
-    let mut writer = BufWriter::new(file);
-
-    for message in messages {
-        writeln!(writer, "[{:.6}]{}", message.timestamp, message.content)?;
+impl AnalyzeToolPage {
+    fn start(&mut self, ctx: &egui::Context, paths: Vec<PathBuf>) {
+        let types = self.save.types.clone();
+        let output = self.save.output.clone();
+        let options = self.options();
+        self.job.spawn_batch(
+            ctx,
+            tr!("analyze.name"),
+            paths,
+            options,
+            move |path, progress| {
+                let output_path = output.resolve(path, analyze::OUTPUT_FORMAT)?;
+                analyze::analyze_file(&types, path, &output_path, progress)?;
+                Ok(vec![output_path])
+            },
+        );
     }
 
-    writer.flush()?;
-    Ok(())
-}
+    fn grid_contents(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        self.files.ui(ui);
 
-fn analyze_main(types: &str, input_file: &str, output_file: &str) {
-    let valid_types: HashSet<String> = types.split(',').map(|s| s.trim().to_string()).collect();
+        ui.label(tr!("analyze.types"));
+        ui.text_edit_singleline(&mut self.save.types);
+        ui.end_row();
 
-    let file = File::open(input_file).unwrap();
-    let reader = BufReader::with_capacity(128 * 1024, file);
+        output_policy_ui(ui, &mut self.save.output);
 
-    let messages = process_logic_data(reader).unwrap();
+        self.watch
+            .ui(ui, &mut self.save.watch_dir, &mut self.save.watching);
 
-    let valid_messages: Vec<_> = messages
-        .into_iter()
-        .filter(|msg| validate_message_type(&msg.content, &valid_types))
-        .collect();
-    write_output(&valid_messages, output_file).unwrap();
+        ui.add_enabled_ui(!self.job.is_running() && !self.files.is_empty(), |ui| {
+            if ui.button(tr!("common.process")).clicked() {
+                self.start(ctx, self.files.paths());
+            }
+        });
+        ui.end_row();
+    }
 }
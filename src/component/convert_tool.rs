@@ -0,0 +1,539 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::{
+    file_encoding_select, load_settings, output_label, output_policy_ui,
+    preview_files_being_dropped, show_page_header, BatchResult, EncodingInspector, FileEncoding,
+    FileQueue, FinishedRun, JobRunner, Settings,
+};
+use crate::add_drop_file;
+use crate::component::{Interface, ToolInfo};
+use crate::core::convert::{self, ConvertOptions, ConvertReport, LineEnding, TargetEncoding};
+use crate::core::OutputPolicy;
+use crate::tr;
+
+static CONVERT_TOOL_PAGE_KEY: &str = "ConvertKey";
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct ConvertToolSave {
+    version: u32,
+    options: ConvertOptions,
+    recursive: bool,
+    /// 文件夹里要转换的文件名，见 [`convert::collect`]
+    patterns: String,
+    output: OutputPolicy,
+    /// 上次列表里的文件和文件夹
+    inputs: Vec<PathBuf>,
+    recent: Vec<PathBuf>,
+}
+
+impl Default for ConvertToolSave {
+    fn default() -> Self {
+        ConvertToolSave {
+            version: 0,
+            options: ConvertOptions::default(),
+            recursive: true,
+            patterns: "*.log *.txt".to_string(),
+            output: OutputPolicy::default(),
+            inputs: Vec::new(),
+            recent: Vec::new(),
+        }
+    }
+}
+
+impl Settings for ConvertToolSave {
+    const VERSION: u32 = 1;
+
+    fn version(&mut self) -> &mut u32 {
+        &mut self.version
+    }
+}
+
+/// 历史记录里保存的处理选项
+#[derive(serde::Deserialize, serde::Serialize)]
+struct RunOptions {
+    options: ConvertOptions,
+    recursive: bool,
+    patterns: String,
+    output: OutputPolicy,
+    /// 单独指定了输入编码的文件
+    #[serde(default)]
+    overrides: Vec<Override>,
+}
+
+/// 单独指定的输入编码，文件按列表里的第几项和相对位置保存，换了目录打开也能对上
+#[derive(serde::Deserialize, serde::Serialize)]
+struct Override {
+    root: usize,
+    path: PathBuf,
+    encoding: FileEncoding,
+}
+
+/// 上一次预览或转换的结果
+struct Report {
+    /// 开始时的 [`RunOptions`] 和列表，变了之后要重新预览才能转换
+    options: String,
+    roots: Vec<PathBuf>,
+    dry_run: bool,
+    files: Vec<(PathBuf, Result<ConvertReport, String>)>,
+}
+
+pub struct ConvertToolPage {
+    save: ConvertToolSave,
+    /// 文件夹在预览时才按通配符展开
+    files: FileQueue,
+    history: Option<Vec<PathBuf>>,
+    job: JobRunner<BatchResult<ConvertReport>>,
+    inspector: EncodingInspector,
+    /// 在报告里单独选的输入编码
+    overrides: HashMap<PathBuf, FileEncoding>,
+    /// 最近开始的任务是不是预览，和开始时的选项
+    dry_run: bool,
+    started_options: String,
+    started_roots: Vec<PathBuf>,
+    report: Option<Report>,
+}
+
+add_drop_file!(ConvertToolPage);
+
+impl eframe::App for ConvertToolPage {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.save.inputs = self.files.paths();
+        self.save.recent = self.files.recent().to_vec();
+        eframe::set_value(storage, CONVERT_TOOL_PAGE_KEY, &self.save);
+    }
+    fn update(&mut self, _ctx: &egui::Context, _frame: &mut eframe::Frame) {}
+}
+
+impl Interface for ConvertToolPage {
+    fn info() -> ToolInfo {
+        ToolInfo {
+            id: "convert",
+            name: "convert.name",
+            description: "convert.description",
+            icon: "🔤",
+        }
+    }
+    fn new(cc: &eframe::CreationContext<'_>) -> Self
+    where
+        Self: Sized,
+    {
+        let save: ConvertToolSave = load_settings(cc.storage, CONVERT_TOOL_PAGE_KEY);
+        // 拖入的文件不按扩展名过滤，文件夹里的文件由通配符过滤
+        let mut files = FileQueue::with_folders(&[]);
+        files.restore(&save.inputs, &save.recent);
+        ConvertToolPage {
+            save,
+            files,
+            history: None,
+            job: JobRunner::default(),
+            inspector: EncodingInspector::default(),
+            overrides: HashMap::new(),
+            dry_run: false,
+            started_options: String::new(),
+            started_roots: Vec::new(),
+            report: None,
+        }
+    }
+    fn new_update<'a>(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        close: Box<dyn FnMut() + 'a>,
+    ) {
+        show_page_header(ui, close);
+
+        egui::Grid::new("convert")
+            .num_columns(2)
+            .spacing([40.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| self.grid_contents(ctx, ui));
+
+        self.job.status_bar(ui);
+        self.report_ui(ui);
+
+        self.get_drop_file(ctx, ui);
+    }
+
+    fn tick(&mut self, _ctx: &egui::Context) {
+        if let Some(Ok(batch)) = self.job.poll() {
+            // 文件夹里展开的文件不在列表上，只记到最近文件里
+            if !self.dry_run {
+                self.files.set_results(&batch);
+            }
+            self.report = Some(Report {
+                options: self.started_options.clone(),
+                roots: self.started_roots.clone(),
+                dry_run: self.dry_run,
+                files: batch
+                    .files
+                    .into_iter()
+                    .map(|(path, ret)| (path, ret.map_err(|e| e.message())))
+                    .collect(),
+            });
+        }
+    }
+
+    fn take_run(&mut self) -> Option<FinishedRun> {
+        // 预览不记到历史里
+        self.job.take_run().filter(|_| !self.dry_run)
+    }
+
+    fn inputs(&self) -> Vec<PathBuf> {
+        self.files.paths()
+    }
+
+    fn options(&self) -> String {
+        self.run_options()
+    }
+
+    fn apply(&mut self, inputs: Vec<PathBuf>, options: &str) -> serde_json::Result<()> {
+        let options: RunOptions = serde_json::from_str(options)?;
+        self.save.options = options.options;
+        self.save.recursive = options.recursive;
+        self.save.patterns = options.patterns;
+        self.save.output = options.output;
+        self.overrides = options
+            .overrides
+            .into_iter()
+            .filter_map(|o| Some((convert::from_root(&inputs, o.root, &o.path)?, o.encoding)))
+            .collect();
+        self.files.replace(inputs);
+        self.report = None;
+        Ok(())
+    }
+
+    /// 打开工作区或者重新运行时也只预览，看过报告再点转换
+    fn run(&mut self, ctx: &egui::Context) {
+        self.preview(ctx);
+    }
+}
+
+impl ConvertToolPage {
+    fn run_options(&self) -> String {
+        // 列表里已经没有的文件的编码不再保存
+        let roots = self.files.paths();
+        let mut overrides: Vec<Override> = self
+            .overrides
+            .iter()
+            .filter_map(|(path, encoding)| {
+                let (root, path) = convert::relative_to_root(&roots, path)?;
+                Some(Override {
+                    root,
+                    path,
+                    encoding: encoding.clone(),
+                })
+            })
+            .collect();
+        overrides.sort_by(|a, b| (a.root, &a.path).cmp(&(b.root, &b.path)));
+        serde_json::to_string(&RunOptions {
+            options: self.save.options.clone(),
+            recursive: self.save.recursive,
+            patterns: self.save.patterns.clone(),
+            output: self.save.output.clone(),
+            overrides,
+        })
+        .unwrap_or_default()
+    }
+
+    /// 报告是当前选项的预览，可以直接转换
+    fn can_convert(&self) -> bool {
+        self.report.as_ref().is_some_and(|report| {
+            report.dry_run
+                && report.options == self.run_options()
+                && report.roots == self.files.paths()
+                && report.files.iter().any(|(_, ret)| ret.is_ok())
+        })
+    }
+
+    fn preview(&mut self, ctx: &egui::Context) {
+        let paths = convert::collect(
+            &self.files.paths(),
+            self.save.recursive,
+            &self.save.patterns,
+        );
+        self.start(ctx, paths, true);
+    }
+
+    /// 只转换预览里成功的文件
+    fn convert(&mut self, ctx: &egui::Context) {
+        let Some(report) = &self.report else {
+            return;
+        };
+        let paths = report
+            .files
+            .iter()
+            .filter(|(_, ret)| ret.is_ok())
+            .map(|(path, _)| path.clone())
+            .collect();
+        self.start(ctx, paths, false);
+    }
+
+    fn start(&mut self, ctx: &egui::Context, paths: Vec<PathBuf>, dry_run: bool) {
+        if self.job.is_running() {
+            return;
+        }
+        let options = self.save.options.clone();
+        let output = self.save.output.clone();
+        let roots = self.files.paths();
+        let overrides = self.overrides.clone();
+        let run_options = self.run_options();
+        self.dry_run = dry_run;
+        self.started_options = run_options.clone();
+        self.started_roots = roots.clone();
+        let run_roots = roots.clone();
+        let name = if dry_run {
+            tr!("convert.job_preview")
+        } else {
+            tr!("convert.job")
+        };
+        self.job
+            .spawn_batch(ctx, name, paths, run_options, move |path, progress| {
+                let mut options = options.clone();
+                if let Some(source) = overrides.get(path) {
+                    options.source = source.clone();
+                }
+                let output = convert::mirror(&output, &roots, path);
+                convert::convert_file(path, &options, &output, dry_run, progress)
+            });
+        self.job.set_roots(run_roots);
+    }
+
+    fn grid_contents(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        self.files.ui(ui);
+
+        ui.label(tr!("convert.patterns"));
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.save.patterns).hint_text("*.log *.txt"))
+                .on_hover_text(tr!("convert.patterns_help"));
+            ui.checkbox(&mut self.save.recursive, tr!("convert.recursive"));
+        });
+        ui.end_row();
+
+        // 编码检查用预览出来的文件
+        let files: Vec<PathBuf> = match &self.report {
+            Some(report) => report.files.iter().map(|(path, _)| path.clone()).collect(),
+            None => self
+                .files
+                .paths()
+                .into_iter()
+                .filter(|path| !path.is_dir())
+                .collect(),
+        };
+        file_encoding_select(
+            ui,
+            &mut self.save.options.source,
+            None,
+            &mut self.inspector,
+            &files,
+        );
+
+        ui.label(tr!("convert.target"));
+        egui::ComboBox::from_id_salt("convert_target")
+            .selected_text(self.save.options.target.name())
+            .show_ui(ui, |ui| {
+                for target in TargetEncoding::ALL {
+                    ui.selectable_value(&mut self.save.options.target, target, target.name());
+                }
+            });
+        ui.end_row();
+
+        ui.label(tr!("convert.line_ending"));
+        ui.horizontal(|ui| {
+            let line_ending = &mut self.save.options.line_ending;
+            ui.radio_value(line_ending, LineEnding::Keep, tr!("convert.keep"));
+            ui.radio_value(line_ending, LineEnding::Lf, "LF");
+            ui.radio_value(line_ending, LineEnding::CrLf, "CRLF");
+        });
+        ui.end_row();
+
+        output_policy_ui(ui, &mut self.save.output);
+
+        ui.horizontal(|ui| {
+            let idle = !self.job.is_running();
+            if ui
+                .add_enabled(
+                    idle && !self.files.is_empty(),
+                    egui::Button::new(tr!("convert.preview")),
+                )
+                .clicked()
+            {
+                self.preview(ctx);
+            }
+            if ui
+                .add_enabled(
+                    idle && self.can_convert(),
+                    egui::Button::new(tr!("convert.convert")),
+                )
+                .on_disabled_hover_text(tr!("convert.preview_first"))
+                .clicked()
+            {
+                self.convert(ctx);
+            }
+        });
+        ui.end_row();
+    }
+
+    fn report_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(report) = &self.report else {
+            return;
+        };
+        ui.separator();
+        if report.files.is_empty() {
+            ui.label(tr!("convert.no_files"));
+            return;
+        }
+        let problems = report
+            .files
+            .iter()
+            .filter(|(_, ret)| match ret {
+                Ok(file) => file.decode_error_count > 0 || file.stats.unmappable > 0,
+                Err(_) => true,
+            })
+            .count();
+        ui.label(if report.dry_run {
+            tr!(
+                "convert.preview_summary",
+                count = report.files.len(),
+                problems = problems
+            )
+        } else {
+            tr!(
+                "convert.done_summary",
+                count = report.files.len(),
+                problems = problems
+            )
+        });
+        if report.dry_run && report.options != self.run_options() {
+            ui.colored_label(ui.visuals().warn_fg_color, tr!("convert.stale"));
+        }
+
+        let mut changed = None;
+        egui::ScrollArea::both()
+            .id_salt("convert_report")
+            .auto_shrink([false, true])
+            .max_height(360.0)
+            .show(ui, |ui| {
+                egui::Grid::new("convert_report")
+                    .num_columns(6)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong(tr!("convert.file"));
+                        ui.strong(tr!("convert.source"));
+                        ui.strong(tr!("convert.line_endings"));
+                        ui.strong(tr!("convert.decode_errors"));
+                        ui.strong(tr!("convert.unmappable"));
+                        ui.strong(tr!("convert.output"));
+                        ui.end_row();
+                        for (i, (path, ret)) in report.files.iter().enumerate() {
+                            ui.label(file_name(path))
+                                .on_hover_text(path.display().to_string());
+                            let mut encode = self.overrides.get(path).cloned();
+                            let detected = ret.as_ref().ok().map(|file| file.source.name());
+                            if source_select(ui, i, &mut encode, detected, report.dry_run) {
+                                changed = Some((path.clone(), encode));
+                            }
+                            match ret {
+                                Ok(file) => file_row(ui, file),
+                                Err(e) => {
+                                    ui.label("");
+                                    ui.label("");
+                                    ui.label("");
+                                    ui.colored_label(
+                                        ui.visuals().error_fg_color,
+                                        format!("✖ {}", e),
+                                    );
+                                }
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+        match changed {
+            Some((path, Some(encode))) => {
+                self.overrides.insert(path, encode);
+            }
+            Some((path, None)) => {
+                self.overrides.remove(&path);
+            }
+            None => {}
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// 报告里单个文件的输入编码，`None` 用页面上的编码；转换后的报告只显示
+fn source_select(
+    ui: &mut egui::Ui,
+    row: usize,
+    encode: &mut Option<FileEncoding>,
+    detected: Option<&str>,
+    editable: bool,
+) -> bool {
+    let detected = detected.unwrap_or("-");
+    if !editable {
+        ui.label(detected);
+        return false;
+    }
+    let text = match encode {
+        Some(encode) => encode.name().to_string(),
+        None => format!("{} ({})", detected, tr!("convert.auto")),
+    };
+    let before = encode.clone();
+    egui::ComboBox::from_id_salt(("convert_source", row))
+        .selected_text(text)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(encode, None, tr!("convert.auto"));
+            for choice in FileEncoding::CHOICES {
+                let name = choice.name().to_string();
+                ui.selectable_value(encode, Some(choice), name);
+            }
+        });
+    *encode != before
+}
+
+fn file_row(ui: &mut egui::Ui, file: &ConvertReport) {
+    let stats = &file.stats;
+    let line_endings: Vec<String> = [("CRLF", stats.crlf), ("LF", stats.lf), ("CR", stats.cr)]
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(name, count)| format!("{} {}", name, count))
+        .collect();
+    ui.label(line_endings.join(", "));
+
+    if file.decode_error_count > 0 {
+        let offsets: Vec<String> = file
+            .decode_errors
+            .iter()
+            .map(|error| error.offset.to_string())
+            .collect();
+        ui.colored_label(
+            ui.visuals().error_fg_color,
+            file.decode_error_count.to_string(),
+        )
+        .on_hover_text(tr!("convert.offsets", offsets = offsets.join(", ")));
+    } else {
+        ui.label("0");
+    }
+    if stats.unmappable > 0 {
+        ui.colored_label(ui.visuals().warn_fg_color, stats.unmappable.to_string());
+    } else {
+        ui.label("0");
+    }
+
+    ui.horizontal(|ui| {
+        if file.written {
+            ui.colored_label(egui::Color32::GREEN, "✔");
+            output_label(ui, &file.output);
+        } else {
+            ui.label(format!("→ {}", file.output.display()));
+        }
+    });
+}
@@ -0,0 +1,156 @@
+//! 文件对话框，本地是系统对话框，网页版是浏览器的文件选择
+
+#[cfg(any_tool)]
+use std::path::PathBuf;
+
+use crate::tr;
+
+/// 路径输入框旁边的浏览按钮选什么，网页版没有浏览按钮
+#[derive(Clone, Copy)]
+#[cfg_attr(any(target_arch = "wasm32", not(any_tool)), allow(dead_code))]
+pub enum Browse<'a> {
+    /// 打开已有文件，按扩展名过滤，为空时不过滤
+    Open(&'a [&'a str]),
+    /// 保存文件，按扩展名过滤
+    Save(&'a [&'a str]),
+    Folder,
+}
+
+/// 对话框里的过滤器名字，`*.log *.txt`
+fn filter_name(extensions: &[&str]) -> String {
+    extensions
+        .iter()
+        .map(|ext| format!("*.{}", ext))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 路径输入框加浏览按钮，路径变了返回 true；网页版没有路径，只有输入框
+pub fn path_edit(ui: &mut egui::Ui, path: &mut String, hint: &str, browse: Browse<'_>) -> bool {
+    #[cfg_attr(target_arch = "wasm32", allow(unused_mut))]
+    let mut changed = ui
+        .add(egui::TextEdit::singleline(path).hint_text(hint))
+        .changed();
+    #[cfg(not(target_arch = "wasm32"))]
+    if ui.button(tr!("dialog.browse")).clicked() {
+        if let Some(picked) = native::browse(path, browse) {
+            *path = picked.display().to_string();
+            changed = true;
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = browse;
+    changed
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::path::{Path, PathBuf};
+
+    use rfd::FileDialog;
+
+    use super::{filter_name, Browse};
+    use crate::tr;
+
+    pub fn with_filters(dialog: FileDialog, extensions: &[&str]) -> FileDialog {
+        if extensions.is_empty() {
+            return dialog;
+        }
+        dialog
+            .add_filter(filter_name(extensions), extensions)
+            .add_filter(tr!("dialog.all_files"), &["*"])
+    }
+
+    /// 从当前路径所在的目录打开对话框
+    pub fn browse(current: &str, browse: Browse<'_>) -> Option<PathBuf> {
+        let current = Path::new(current);
+        let mut dialog = FileDialog::new();
+        let dir = if current.is_dir() {
+            Some(current)
+        } else {
+            current.parent().filter(|dir| dir.is_dir())
+        };
+        if let Some(dir) = dir {
+            dialog = dialog.set_directory(dir);
+        }
+        match browse {
+            Browse::Open(extensions) => with_filters(dialog, extensions).pick_file(),
+            Browse::Save(extensions) => {
+                if let Some(name) = current.file_name() {
+                    dialog = dialog.set_file_name(name.to_string_lossy());
+                }
+                with_filters(dialog, extensions).save_file()
+            }
+            Browse::Folder => dialog.pick_folder(),
+        }
+    }
+}
+
+/// 添加文件夹的按钮，返回选中的文件夹；网页版不能访问文件夹，不显示
+#[cfg(any_tool)]
+pub fn folder_button(ui: &mut egui::Ui) -> Option<PathBuf> {
+    #[cfg(not(target_arch = "wasm32"))]
+    if ui.button(tr!("dialog.add_folder")).clicked() {
+        return native::browse("", Browse::Folder);
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = ui;
+    None
+}
+
+/// 选择输入文件的按钮
+///
+/// 本地直接返回选中的路径；网页版要等浏览器读完文件内容，放进 [`crate::core::fs`]
+/// 之后在后面的帧里返回用文件名做的路径
+#[cfg(any_tool)]
+#[derive(Default)]
+pub struct OpenFiles {
+    #[cfg(target_arch = "wasm32")]
+    rx: Option<std::sync::mpsc::Receiver<Vec<PathBuf>>>,
+}
+
+#[cfg(any_tool)]
+impl OpenFiles {
+    pub fn button(&mut self, ui: &mut egui::Ui, extensions: &[&str]) -> Option<Vec<PathBuf>> {
+        let clicked = ui.button(tr!("dialog.browse")).clicked();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if !clicked {
+                return None;
+            }
+            native::with_filters(rfd::FileDialog::new(), extensions).pick_files()
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            if clicked {
+                self.pick(ui.ctx(), extensions);
+            }
+            let paths = self.rx.as_ref()?.try_recv().ok()?;
+            self.rx = None;
+            Some(paths)
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn pick(&mut self, ctx: &egui::Context, extensions: &[&str]) {
+        let mut dialog = rfd::AsyncFileDialog::new();
+        if !extensions.is_empty() {
+            dialog = dialog.add_filter(filter_name(extensions), extensions);
+        }
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.rx = Some(rx);
+        let ctx = ctx.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let mut paths = Vec::new();
+            for file in dialog.pick_files().await.unwrap_or_default() {
+                let path = PathBuf::from(file.file_name());
+                crate::core::fs::insert(path.clone(), file.read().await.into());
+                paths.push(path);
+            }
+            tx.send(paths).ok();
+            ctx.request_repaint();
+        });
+    }
+}
@@ -1,87 +1,112 @@
-use serde::Serialize;
-use std::io::Read;
-use std::{
-    fs::File,
-    sync::mpsc::{self, Receiver, Sender},
-    thread,
-};
+use std::path::PathBuf;
 
-use super::{file_encoding_proc, file_encoding_select, show_page_header, FileEncoding};
+use super::{
+    file_encoding_select, load_settings, output_policy_ui, show_page_header, BatchResult,
+    EncodingInspector, FileEncoding, FileQueue, FinishedRun, FolderWatch, JobRunner, Settings,
+};
 use crate::add_drop_file;
 use crate::component::preview_files_being_dropped;
-use crate::component::Interface;
+use crate::component::{Interface, ToolInfo};
+use crate::core::encoding;
+use crate::core::hardfault::{self, CPURegs};
+use crate::core::OutputPolicy;
+use crate::tr;
 
 static HARDFAULT_TOOL_PAGE_KEY: &str = "HardfaultKey";
 
-#[derive(Debug, Default, Serialize, Clone)]
-pub struct CPURegs {
-    regs: [String; 32],
-    header: String,
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct HardfaultToolSave {
+    version: u32,
+    file_encoding: FileEncoding,
+    save_normalized: bool,
+    /// 监控文件夹时写出的寄存器报告，也用于 utf8 副本
+    output: OutputPolicy,
+    /// 上次列表里的文件
+    inputs: Vec<PathBuf>,
+    recent: Vec<PathBuf>,
+    watch_dir: String,
+    watching: bool,
 }
 
-static REG_NAME: [&str; 32] = [
-    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
-    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
-    "t5", "t6",
-];
-
-impl CPURegs {
-    fn display(&self) -> String {
-        let mut ret = String::new();
-        ret.push_str(&format!("{}\n", self.header));
-        for (i, reg) in self.regs.iter().enumerate() {
-            if i > 0 && (i % 4 == 0) {
-                ret.push('\n');
-            }
-            ret.push_str(&format!("{}: {}, ", REG_NAME[i], reg));
-        }
-        ret
-    }
+/// 历史记录里保存的处理选项
+#[derive(serde::Deserialize, serde::Serialize)]
+struct RunOptions {
+    file_encoding: FileEncoding,
+    #[serde(default)]
+    save_normalized: bool,
+    output: OutputPolicy,
+    report: bool,
+    /// 显示的寄存器组，处理完之后恢复
+    #[serde(default)]
+    selected: usize,
 }
 
-#[derive(Default, serde::Deserialize, serde::Serialize)]
-struct HardfaultToolSave {
-    visable: bool,
+impl Settings for HardfaultToolSave {
+    /// 1: 保存编码和文件列表，0 只有没用到的 `visable`
+    const VERSION: u32 = 1;
+
+    fn version(&mut self) -> &mut u32 {
+        &mut self.version
+    }
 }
 
 pub struct HardfaultToolPage {
     save: HardfaultToolSave,
-    path: String,
-    history: Option<String>,
-    channel: (Sender<Vec<CPURegs>>, Receiver<Vec<CPURegs>>),
-    doing: bool,
-    regs: Vec<CPURegs>,
+    files: FileQueue,
+    history: Option<Vec<PathBuf>>,
+    job: JobRunner<BatchResult<Vec<CPURegs>>>,
+    watch: FolderWatch,
+    inspector: EncodingInspector,
+    /// 所有文件里解析出的寄存器组和所在的文件名
+    regs: Vec<(String, CPURegs)>,
     selected: usize,
-    file_encoding: FileEncoding,
+    /// 打开工作区或者重新运行时要恢复的 `selected`
+    pending_selected: Option<usize>,
+    /// 处理时写出寄存器报告，来自历史记录或工作区的选项
+    report: bool,
 }
 
 add_drop_file!(HardfaultToolPage);
 
 impl eframe::App for HardfaultToolPage {
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.save.inputs = self.files.paths();
+        self.save.recent = self.files.recent().to_vec();
         eframe::set_value(storage, HARDFAULT_TOOL_PAGE_KEY, &self.save);
     }
     fn update(&mut self, _ctx: &egui::Context, _frame: &mut eframe::Frame) {}
 }
 
 impl Interface for HardfaultToolPage {
+    fn info() -> ToolInfo {
+        ToolInfo {
+            id: "hardfault",
+            name: "hardfault.name",
+            description: "hardfault.description",
+            icon: "🐞",
+        }
+    }
     fn new(cc: &eframe::CreationContext<'_>) -> Self
     where
         Self: Sized,
     {
         let mut page = HardfaultToolPage {
-            save: HardfaultToolSave::default(),
-            path: String::new(),
+            save: load_settings(cc.storage, HARDFAULT_TOOL_PAGE_KEY),
+            files: FileQueue::new(&["log", "txt"]),
             history: None,
-            channel: mpsc::channel(),
-            doing: false,
+            job: JobRunner::default(),
+            watch: FolderWatch::new(&["log", "txt"], &[hardfault::REPORT_FORMAT]),
+            inspector: EncodingInspector::default(),
             regs: Vec::new(),
             selected: 0,
-            file_encoding: FileEncoding::UTF8,
+            pending_selected: None,
+            report: false,
         };
 
-        if let Some(storage) = cc.storage {
-            page.save = eframe::get_value(storage, HARDFAULT_TOOL_PAGE_KEY).unwrap_or_default();
+        page.files.restore(&page.save.inputs, &page.save.recent);
+        if page.save.watching {
+            page.watch.start(&cc.egui_ctx, &page.save.watch_dir);
         }
         page
     }
@@ -102,154 +127,149 @@ impl Interface for HardfaultToolPage {
         // for reg in &self.regs {
         //     ui.label(reg.display());
         // }
-        if self.regs.len() > 0 {
-            ui.label(self.regs[self.selected].display());
+        if !self.regs.is_empty() {
+            ui.label(self.regs[self.selected].1.display());
         }
 
-        if let Ok(regs) = self.channel.1.try_recv() {
-            self.doing = false;
-            self.regs = regs;
-            self.selected = 0;
-        }
+        self.job.status_bar(ui);
 
         self.get_drop_file(ctx, ui);
     }
+
+    fn tick(&mut self, ctx: &egui::Context) {
+        if let Some(Ok(batch)) = self.job.poll() {
+            self.files.set_results(&batch);
+            self.regs = batch
+                .files
+                .into_iter()
+                .filter_map(|(path, ret)| Some((path, ret.ok()?)))
+                .flat_map(|(path, regs)| {
+                    let name = path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    regs.into_iter().map(move |reg| (name.clone(), reg))
+                })
+                .collect();
+            self.selected = self
+                .pending_selected
+                .take()
+                .filter(|&selected| selected < self.regs.len())
+                .unwrap_or(0);
+        }
+        if let Some(paths) = self.watch.poll(&mut self.files, self.job.is_running()) {
+            self.start(ctx, paths, true);
+        }
+    }
+
+    fn take_run(&mut self) -> Option<FinishedRun> {
+        self.job.take_run()
+    }
+
+    fn inputs(&self) -> Vec<PathBuf> {
+        self.files.paths()
+    }
+
+    fn options(&self) -> String {
+        self.run_options(false)
+    }
+
+    fn apply(&mut self, inputs: Vec<PathBuf>, options: &str) -> serde_json::Result<()> {
+        let options: RunOptions = serde_json::from_str(options)?;
+        self.save.file_encoding = options.file_encoding;
+        self.save.save_normalized = options.save_normalized;
+        self.save.output = options.output;
+        self.report = options.report;
+        self.pending_selected = Some(options.selected);
+        self.files.replace(inputs);
+        Ok(())
+    }
+
+    fn run(&mut self, ctx: &egui::Context) {
+        self.start(ctx, self.files.paths(), self.report);
+    }
 }
 
 impl HardfaultToolPage {
-    fn grid_contents(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) {
-        ui.label("文件地址");
-        ui.text_edit_singleline(&mut self.path);
-        ui.end_row();
+    fn run_options(&self, report: bool) -> String {
+        serde_json::to_string(&RunOptions {
+            file_encoding: self.save.file_encoding.clone(),
+            save_normalized: self.save.save_normalized,
+            output: self.save.output.clone(),
+            report,
+            selected: self.selected,
+        })
+        .unwrap_or_default()
+    }
 
-        ui.label("选择需要显示的寄存器组");
+    /// `report` 为真时在日志旁边写出寄存器报告，监控文件夹时使用
+    fn start(&mut self, ctx: &egui::Context, paths: Vec<PathBuf>, report: bool) {
+        let encode = self.save.file_encoding.clone();
+        let save_normalized = self.save.save_normalized;
+        let output = self.save.output.clone();
+        let options = self.run_options(report);
+        // 要写 utf8 副本时每个文件读两遍
+        let passes = if save_normalized { 2 } else { 1 };
+        self.job.spawn_batch_passes(
+            ctx,
+            tr!("hardfault.name"),
+            paths,
+            options,
+            passes,
+            move |path, progress| {
+                if save_normalized && report {
+                    output
+                        .check_formats(&[encoding::NORMALIZED_FORMAT, hardfault::REPORT_FORMAT])?;
+                }
+                if save_normalized {
+                    encoding::save_normalized(path, &encode, &output, progress)?;
+                }
+                let regs = hardfault::parse_file(path, &encode, progress)?;
+                if report {
+                    hardfault::report_file(path, &regs, &output)?;
+                }
+                Ok(regs)
+            },
+        );
+    }
+
+    fn grid_contents(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        self.files.ui(ui);
+
+        ui.label(tr!("hardfault.select_group"));
         ui.end_row();
-        ui.add_enabled_ui(self.regs.len() > 0, |ui| {
-            egui::ComboBox::from_label("寄存器组")
-                .selected_text(format!("{}", self.selected))
+        ui.add_enabled_ui(!self.regs.is_empty(), |ui| {
+            egui::ComboBox::from_label(tr!("hardfault.group"))
+                .selected_text(match self.regs.get(self.selected) {
+                    Some((name, _)) => format!("{} ({})", self.selected, name),
+                    None => format!("{}", self.selected),
+                })
                 .show_ui(ui, |ui| {
-                    let len = self.regs.len();
-                    for i in 0..len {
-                        ui.selectable_value(&mut self.selected, i, format!("{}", i));
+                    for (i, (name, _)) in self.regs.iter().enumerate() {
+                        ui.selectable_value(&mut self.selected, i, format!("{} ({})", i, name));
                     }
                 });
         });
         ui.end_row();
 
-        file_encoding_select(ui, &mut self.file_encoding);
-
-        ui.add_enabled_ui(!self.doing && self.path.len() > 0, |ui| {
-            if ui.button("处理").clicked() {
-                self.doing = true;
-                let tx = self.channel.0.clone();
-                let path = self.path.clone();
-                let encode = self.file_encoding.clone();
-                thread::spawn(move || {
-                    file_encoding_proc(&path, &encode);
-                    let ret = hardfault_tool(path);
-                    tx.send(ret).unwrap();
-                });
-            }
-        });
-        ui.end_row();
-    }
-}
+        file_encoding_select(
+            ui,
+            &mut self.save.file_encoding,
+            Some(&mut self.save.save_normalized),
+            &mut self.inspector,
+            &self.files.paths(),
+        );
 
-fn hardfault_tool(path: String) -> Vec<CPURegs> {
-    let start_flag1 = "ERR:";
-    let start_flag2 = "EPC:";
-    let start_flag3 = "WDT_RST:";
-
-    let empty_str = "0xXXXXXXXX";
-    let mut regs = CPURegs::default();
-    let mut reg_vec = Vec::new();
-
-    if let Ok(mut file) = File::open(&path) {
-        println!("open {} success", &path);
-
-        let mut index = 0;
-        let mut state = 0; // 1: epc, 2: wdt
-
-        let mut buf = Vec::new();
-        file.read_to_end(&mut buf).unwrap();
-        let lines = String::from_utf8(buf.to_vec()).unwrap();
-
-        for line in lines.split(|c| c == '\r' || c == '\n') {
-            // println!("line: {}, state:{}", line, state);
-            match state {
-                1 => {
-                    for l in line.split(' ') {
-                        if l.len() == 0 {
-                            continue;
-                        }
-                        if let Ok(reg) = u32::from_str_radix(l, 16) {
-                            regs.regs[index] = format!("{:#010X}", reg);
-                        } else {
-                            state = 3;
-                        }
-
-                        index += 1;
-                    }
-                    if index >= 32 {
-                        state = 3;
-                        reg_vec.push(regs.clone());
-                    }
-                }
-                2 => {
-                    for l in line.split(' ') {
-                        match index {
-                            0 => {
-                                regs.regs[index] = empty_str.to_string();
-                                index += 1;
-                            }
-                            2 => {
-                                while index < 4 {
-                                    regs.regs[index] = empty_str.to_string();
-                                    index += 1;
-                                }
-                            }
-                            18 => {
-                                while index < 28 {
-                                    regs.regs[index] = empty_str.to_string();
-                                    index += 1;
-                                }
-                            }
-                            _ => {}
-                        }
-                        if l.len() == 0 {
-                            continue;
-                        }
-
-                        if let Ok(reg) = u32::from_str_radix(l, 16) {
-                            regs.regs[index] = format!("{:#010X}", reg);
-                        } else {
-                            state = 3;
-                        }
-
-                        index += 1;
-                    }
-                    if index >= 19 {
-                        state = 3;
-                        reg_vec.push(regs.clone());
-                    }
-                }
-                _ => {}
-            }
+        output_policy_ui(ui, &mut self.save.output);
 
-            if line.contains(start_flag1) && line.contains(start_flag2) {
-                regs.header = line.to_string();
-                state = 1;
-                index = 0;
-                // println!("EPC");
-            }
-            if line.contains(start_flag3) {
-                regs.header = line.to_string();
-                state = 2;
-                index = 0;
-                // println!("WDT");
+        self.watch
+            .ui(ui, &mut self.save.watch_dir, &mut self.save.watching);
+
+        ui.add_enabled_ui(!self.job.is_running() && !self.files.is_empty(), |ui| {
+            if ui.button(tr!("common.process")).clicked() {
+                self.start(ctx, self.files.paths(), false);
             }
-        }
+        });
+        ui.end_row();
     }
-
-    reg_vec
 }
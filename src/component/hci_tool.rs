@@ -1,57 +1,101 @@
-use std::{
-    process::Command,
-    sync::mpsc::{self, Receiver, Sender},
-    thread,
-};
+use std::path::PathBuf;
 
+use super::job::JobError;
 use super::{
-    file_encoding_proc, file_encoding_select, preview_files_being_dropped, show_page_header,
-    FileEncoding,
+    file_encoding_select, load_settings, output_policy_ui, preview_files_being_dropped,
+    show_page_header, BatchResult, EncodingInspector, FileEncoding, FileQueue, FinishedRun,
+    FolderWatch, JobRunner, Settings,
 };
-use crate::{add_drop_file, component::Interface};
+use crate::add_drop_file;
+use crate::component::{Interface, ToolInfo};
+use crate::core::{encoding, hci, Error, OutputPolicy};
+use crate::tr;
 
 static HCI_TOOL_PAGE_KEY: &str = "HciKey";
 
 #[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct HciToolSave {
-    visable: bool,
-    program: String,
+    version: u32,
+    file_encoding: FileEncoding,
+    save_normalized: bool,
+    output: OutputPolicy,
+    /// 上次列表里的文件
+    inputs: Vec<PathBuf>,
+    recent: Vec<PathBuf>,
+    watch_dir: String,
+    watching: bool,
+}
+
+/// 历史记录里保存的处理选项
+#[derive(serde::Deserialize, serde::Serialize)]
+struct RunOptions {
+    file_encoding: FileEncoding,
+    #[serde(default)]
+    save_normalized: bool,
+    output: OutputPolicy,
+}
+
+impl Settings for HciToolSave {
+    /// 2: 内置 btsnoop 转换，不再保存 log2cfa 路径；1: 保存编码和文件列表；0 只有 log2cfa 路径
+    const VERSION: u32 = 2;
+
+    fn version(&mut self) -> &mut u32 {
+        &mut self.version
+    }
 }
 
 pub struct HciToolPage {
     save: HciToolSave,
-    doing: bool,
-    channel: (Sender<bool>, Receiver<bool>),
-    path: String,
-    history: Option<String>,
-    file_encoding: FileEncoding,
+    job: JobRunner<BatchResult<Vec<PathBuf>>>,
+    files: FileQueue,
+    history: Option<Vec<PathBuf>>,
+    watch: FolderWatch,
+    inspector: EncodingInspector,
+    /// 上次处理时有文件不是 utf8，提示选择编码
+    not_utf8: bool,
 }
 
 add_drop_file!(HciToolPage);
 
 impl eframe::App for HciToolPage {
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.save.inputs = self.files.paths();
+        self.save.recent = self.files.recent().to_vec();
         eframe::set_value(storage, HCI_TOOL_PAGE_KEY, &self.save);
     }
     fn update(&mut self, _ctx: &egui::Context, _frame: &mut eframe::Frame) {}
 }
 
 impl Interface for HciToolPage {
+    fn info() -> ToolInfo {
+        ToolInfo {
+            id: "hci",
+            name: "hci.name",
+            description: "hci.description",
+            icon: "📶",
+        }
+    }
     fn new(cc: &eframe::CreationContext<'_>) -> Self
     where
         Self: Sized,
     {
         let mut page = HciToolPage {
-            save: HciToolSave::default(),
-            doing: false,
-            channel: mpsc::channel(),
-            path: String::new(),
+            save: load_settings(cc.storage, HCI_TOOL_PAGE_KEY),
+            job: JobRunner::default(),
+            files: FileQueue::new(&["log", "txt"]),
             history: None,
-            file_encoding: FileEncoding::UTF8,
+            watch: FolderWatch::new(
+                &["log", "txt"],
+                &[hci::BTSNOOP_FORMAT, encoding::NORMALIZED_FORMAT],
+            ),
+            inspector: EncodingInspector::default(),
+            not_utf8: false,
         };
 
-        if let Some(storage) = cc.storage {
-            page.save = eframe::get_value(storage, HCI_TOOL_PAGE_KEY).unwrap_or_default();
+        page.files.restore(&page.save.inputs, &page.save.recent);
+        if page.save.watching {
+            page.watch.start(&cc.egui_ctx, &page.save.watch_dir);
         }
         page
     }
@@ -63,7 +107,7 @@ impl Interface for HciToolPage {
     ) {
         show_page_header(ui, close);
 
-        ui.heading("HCI Tool");
+        ui.heading(tr!("hci.name"));
 
         egui::Grid::new("hci")
             .num_columns(2)
@@ -71,93 +115,114 @@ impl Interface for HciToolPage {
             .striped(true)
             .show(ui, |ui| self.grid_contents(ctx, ui));
 
-        if let Ok(status) = self.channel.1.try_recv() {
-            self.doing = false;
-            if !status {
-                self.file_encoding = FileEncoding::Other;
-            }
-        }
+        self.job.status_bar(ui);
 
         self.get_drop_file(ctx, ui);
     }
-}
 
-fn hci_file_preproc(path: &str, tx: Sender<bool>, encode: &FileEncoding) {
-    use regex::*;
-    use std::fs;
-
-    let content = match fs::read_to_string(path) {
-        Ok(c) => c,
-        Err(_) => {
-            tx.send(false).unwrap();
-            return;
+    fn tick(&mut self, ctx: &egui::Context) {
+        if let Some(Ok(batch)) = self.job.poll() {
+            self.files.set_results(&batch);
+            self.not_utf8 = batch
+                .files
+                .iter()
+                .any(|(_, ret)| matches!(ret, Err(JobError::Error(Error::NotUtf8))));
+        }
+        if let Some(paths) = self.watch.poll(&mut self.files, self.job.is_running()) {
+            self.start(ctx, paths);
         }
-    };
+    }
+
+    fn take_run(&mut self) -> Option<FinishedRun> {
+        self.job.take_run()
+    }
 
-    // 定义正则表达式
-    let re = Regex::new(r"\(\d{2}:\d{2}:\d{2}\.\d{3}\)").unwrap();
-    // 替换匹配的字符串，前面添加回车符
-    let modified_content = re.replace_all(&content, "");
+    fn inputs(&self) -> Vec<PathBuf> {
+        self.files.paths()
+    }
 
-    let re = Regex::new(r"\[\d{2}:\d{2}:\d{2}\.\d{3}\]").unwrap();
+    fn options(&self) -> String {
+        serde_json::to_string(&RunOptions {
+            file_encoding: self.save.file_encoding.clone(),
+            save_normalized: self.save.save_normalized,
+            output: self.save.output.clone(),
+        })
+        .unwrap_or_default()
+    }
 
-    let mut result = String::new();
-    for line in modified_content.lines() {
-        if line.len() == 0 {
-            continue;
-        }
-        if re.is_match(line) {
-            if ((line.contains("CMD ") || line.contains("EVT ") || line.contains("ACL "))
-                && (line.contains(" => ") || line.contains(" <= ")))
-                || (line.contains("MSG ") && (line.contains(" -> ") || line.contains(" <- ")))
-            {
-                result.push_str(re.replace_all(line, "\n$0").as_ref());
-            } else {
-                result.push_str(re.replace_all(line, "").as_ref());
-            }
-        } else {
-            result.push_str(line);
-        }
-        result.push_str("\n");
+    fn apply(&mut self, inputs: Vec<PathBuf>, options: &str) -> serde_json::Result<()> {
+        let options: RunOptions = serde_json::from_str(options)?;
+        self.save.file_encoding = options.file_encoding;
+        self.save.save_normalized = options.save_normalized;
+        self.save.output = options.output;
+        self.files.replace(inputs);
+        Ok(())
     }
 
-    if *encode == FileEncoding::UTF8 {
-        fs::rename(path, &format!("{}.old", path)).unwrap();
+    fn run(&mut self, ctx: &egui::Context) {
+        self.start(ctx, self.files.paths());
     }
-    let result: String = result.chars().filter(|c| *c < 128 as char).collect();
-    fs::write(path, result).expect("Failed to write to the file");
 }
 
 impl HciToolPage {
-    fn grid_contents(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) {
-        ui.label("log2cfa 路径（会保存）");
-        ui.text_edit_singleline(&mut self.save.program);
-        ui.end_row();
-
-        ui.label("转换文件路径");
-        ui.text_edit_singleline(&mut self.path);
-        ui.end_row();
-
-        file_encoding_select(ui, &mut self.file_encoding);
-
-        ui.add_enabled_ui(
-            !self.doing && self.path.len() > 0 && self.save.program.len() > 0,
-            |ui| {
-                if ui.button("处理").clicked() {
-                    self.doing = true;
-                    let tx = self.channel.0.clone();
-                    let program = self.save.program.clone();
-                    let path = self.path.clone();
-                    let encode = self.file_encoding.clone();
-                    thread::spawn(move || {
-                        file_encoding_proc(&path, &encode);
-                        hci_file_preproc(&path, tx.clone(), &encode);
-                        Command::new(program).arg(path).output().unwrap();
-                        tx.send(true).unwrap();
-                    });
+    fn start(&mut self, ctx: &egui::Context, paths: Vec<PathBuf>) {
+        let encode = self.save.file_encoding.clone();
+        let save_normalized = self.save.save_normalized;
+        let output = self.save.output.clone();
+        let options = self.options();
+        // 要写 utf8 副本时每个文件读两遍
+        let passes = if save_normalized { 2 } else { 1 };
+        self.job.spawn_batch_passes(
+            ctx,
+            tr!("hci.name"),
+            paths,
+            options,
+            passes,
+            move |path, progress| {
+                let mut outputs = Vec::new();
+                if save_normalized {
+                    output.check_formats(&[encoding::NORMALIZED_FORMAT, hci::BTSNOOP_FORMAT])?;
+                    outputs.push(encoding::save_normalized(path, &encode, &output, progress)?);
                 }
+                outputs.push(hci::convert_file(path, &encode, &output, progress)?);
+                Ok(outputs)
             },
         );
+    }
+
+    fn grid_contents(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        self.files.ui(ui);
+
+        file_encoding_select(
+            ui,
+            &mut self.save.file_encoding,
+            Some(&mut self.save.save_normalized),
+            &mut self.inspector,
+            &self.files.paths(),
+        );
+
+        // 不替用户改编码，选了别的编码之后就不再提示
+        if self.not_utf8 && self.save.file_encoding == FileEncoding::UTF8 {
+            ui.label("");
+            ui.horizontal(|ui| {
+                ui.colored_label(ui.visuals().warn_fg_color, tr!("hci.not_utf8"));
+                if ui.button(tr!("hci.use_other")).clicked() {
+                    self.save.file_encoding = FileEncoding::Other;
+                }
+            });
+            ui.end_row();
+        }
+
+        output_policy_ui(ui, &mut self.save.output);
+
+        self.watch
+            .ui(ui, &mut self.save.watch_dir, &mut self.save.watching);
+
+        ui.add_enabled_ui(!self.job.is_running() && !self.files.is_empty(), |ui| {
+            if ui.button(tr!("common.process")).clicked() {
+                self.start(ctx, self.files.paths());
+            }
+        });
         ui.end_row();
     }
 }
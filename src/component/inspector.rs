@@ -0,0 +1,164 @@
+//! 编码检查窗口：列出最可能的几个编码和开头几行的预览，选一个用来处理
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use egui::text::LayoutJob;
+use egui::{FontId, TextFormat};
+
+use super::FileEncoding;
+use crate::core::encoding::{self, Candidate, PreviewSpan};
+use crate::tr;
+
+/// 最多列出的候选编码
+const CANDIDATES: usize = 5;
+
+/// 每个候选预览的行数
+const PREVIEW_LINES: usize = 8;
+
+#[derive(Default)]
+pub struct EncodingInspector {
+    open: bool,
+    path: Option<PathBuf>,
+    /// 检查结果，出错时保存错误信息
+    result: Option<Result<Vec<Candidate>, String>>,
+}
+
+impl EncodingInspector {
+    /// 打开窗口的按钮，列表为空时不可用
+    pub fn button(&mut self, ui: &mut egui::Ui, files: &[PathBuf]) {
+        let clicked = ui
+            .add_enabled(!files.is_empty(), egui::Button::new("🔍"))
+            .on_hover_text(tr!("encoding.inspect"))
+            .clicked();
+        if clicked {
+            self.open = true;
+            match &self.path {
+                Some(path) if files.contains(path) => {}
+                _ => self.inspect(files[0].clone()),
+            }
+        }
+    }
+
+    fn inspect(&mut self, path: PathBuf) {
+        self.result = Some(
+            encoding::inspect(&path, CANDIDATES, PREVIEW_LINES).map_err(|err| err.to_string()),
+        );
+        self.path = Some(path);
+    }
+
+    /// 检查窗口，点“使用”后把 `encode` 改成选中的编码
+    pub fn show(&mut self, ui: &egui::Ui, files: &[PathBuf], encode: &mut FileEncoding) {
+        if !self.open {
+            return;
+        }
+        let mut open = true;
+        let mut inspect = None;
+        egui::Window::new(tr!("encoding.inspector_title"))
+            .id(ui.id().with("encoding_inspector"))
+            .open(&mut open)
+            .default_width(560.0)
+            .vscroll(true)
+            .show(ui.ctx(), |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr!("encoding.inspect_file"));
+                    egui::ComboBox::from_id_salt("encoding_inspect_file")
+                        .selected_text(self.path.as_deref().map(file_name).unwrap_or_default())
+                        .show_ui(ui, |ui| {
+                            for path in files {
+                                let selected = self.path.as_ref() == Some(path);
+                                if ui.selectable_label(selected, file_name(path)).clicked() {
+                                    inspect = Some(path.clone());
+                                }
+                            }
+                        });
+                    if let Some(path) = &self.path {
+                        if ui.button(tr!("encoding.reinspect")).clicked() {
+                            inspect = Some(path.clone());
+                        }
+                    }
+                });
+                ui.weak(tr!("encoding.invalid_bytes_help"));
+                ui.separator();
+
+                match &self.result {
+                    None => {}
+                    Some(Err(err)) => {
+                        ui.colored_label(ui.visuals().error_fg_color, err);
+                    }
+                    Some(Ok(candidates)) if candidates.is_empty() => {
+                        ui.label(tr!("encoding.no_candidates"));
+                    }
+                    Some(Ok(candidates)) => {
+                        for candidate in candidates {
+                            candidate_ui(ui, candidate, encode);
+                        }
+                    }
+                }
+            });
+        self.open = open;
+        if let Some(path) = inspect {
+            self.inspect(path);
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn candidate_ui(ui: &mut egui::Ui, candidate: &Candidate, encode: &mut FileEncoding) {
+    let name = candidate.encoding.name();
+    ui.group(|ui| {
+        ui.horizontal(|ui| {
+            ui.strong(name);
+            ui.label(tr!(
+                "encoding.confidence",
+                value = format!("{:.0}", candidate.confidence)
+            ));
+            let invalid = candidate
+                .preview
+                .iter()
+                .filter(|span| matches!(span, PreviewSpan::Invalid(_)))
+                .count();
+            if invalid > 0 {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    tr!("encoding.invalid_count", count = invalid),
+                );
+            }
+            let selected = encode.charset() == Some(candidate.encoding);
+            if ui.selectable_label(selected, tr!("encoding.use")).clicked() {
+                *encode = FileEncoding::Named(name.to_string());
+            }
+        });
+        ui.label(preview_job(ui, &candidate.preview));
+    });
+}
+
+/// 预览文字，无法解码的字节显示成 `\xAB` 并标红
+fn preview_job(ui: &egui::Ui, spans: &[PreviewSpan]) -> LayoutJob {
+    let font = FontId::monospace(12.0);
+    let text = TextFormat::simple(font.clone(), ui.visuals().text_color());
+    let invalid = TextFormat {
+        background: ui.visuals().error_fg_color.gamma_multiply(0.2),
+        ..TextFormat::simple(font, ui.visuals().error_fg_color)
+    };
+    let mut job = LayoutJob::default();
+    for span in spans {
+        match span {
+            PreviewSpan::Text(s) => job.append(s, 0.0, text.clone()),
+            PreviewSpan::Invalid(bytes) => {
+                let mut hex = String::new();
+                for b in bytes {
+                    let _ = write!(hex, "\\x{:02X}", b);
+                }
+                job.append(&hex, 0.0, invalid.clone());
+            }
+        }
+    }
+    job
+}
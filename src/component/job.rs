@@ -0,0 +1,416 @@
+//! 页面共用的后台任务
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+use web_time::Instant;
+
+use super::{output_label, FinishedRun};
+use crate::console::{self, JobScope};
+#[cfg(feature = "hardfault")]
+use crate::core::hardfault::CPURegs;
+use crate::core::{self, Progress};
+use crate::i18n;
+use crate::tr;
+
+/// 后台任务结果中写出的文件，会显示在状态栏里
+pub trait JobOutputs {
+    fn outputs(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// 是否全部成功，批量任务里有文件失败时为 false
+    fn succeeded(&self) -> bool {
+        true
+    }
+
+    /// 批量任务中途被取消，剩下的文件没有处理
+    fn cancelled(&self) -> bool {
+        false
+    }
+}
+
+impl JobOutputs for Vec<PathBuf> {
+    fn outputs(&self) -> Vec<PathBuf> {
+        self.clone()
+    }
+}
+
+#[cfg(feature = "hardfault")]
+impl JobOutputs for Vec<CPURegs> {}
+
+#[cfg(feature = "convert")]
+impl JobOutputs for core::convert::ConvertReport {
+    fn outputs(&self) -> Vec<PathBuf> {
+        if self.written {
+            vec![self.output.clone()]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// 批量处理的结果，每个文件单独记录成功或失败
+pub struct BatchResult<T> {
+    pub files: Vec<(PathBuf, Result<T, JobError>)>,
+}
+
+impl<T: JobOutputs> JobOutputs for BatchResult<T> {
+    fn outputs(&self) -> Vec<PathBuf> {
+        self.files
+            .iter()
+            .filter_map(|(_, ret)| ret.as_ref().ok())
+            .flat_map(|ret| ret.outputs())
+            .collect()
+    }
+
+    /// 和命令行一样，输出已经存在而跳过的文件不算失败
+    fn succeeded(&self) -> bool {
+        self.files
+            .iter()
+            .all(|(_, ret)| matches!(ret, Ok(_) | Err(JobError::Error(core::Error::Skipped(_)))))
+    }
+
+    fn cancelled(&self) -> bool {
+        self.files
+            .iter()
+            .any(|(_, ret)| matches!(ret, Err(JobError::Error(core::Error::Cancelled))))
+    }
+}
+
+#[derive(Debug)]
+pub enum JobError {
+    Error(core::Error),
+    /// 处理线程 panic 了
+    Panic(String),
+}
+
+impl std::fmt::Display for JobError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobError::Error(e) => write!(f, "{}", e),
+            JobError::Panic(msg) => write!(f, "panicked: {}", msg),
+        }
+    }
+}
+
+impl JobError {
+    /// 界面上显示的错误，跟随当前语言
+    pub fn message(&self) -> String {
+        match self {
+            JobError::Error(e) => i18n::error_message(e),
+            JobError::Panic(msg) => tr!("job.panicked", message = msg),
+        }
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+struct RunningJob<T> {
+    name: String,
+    progress: Progress,
+    started: Instant,
+    rx: Receiver<Result<T, JobError>>,
+    /// 批量任务的输入和选项，结束后生成 [`FinishedRun`]
+    run: Option<RunInputs>,
+}
+
+struct RunInputs {
+    inputs: Vec<PathBuf>,
+    roots: Vec<PathBuf>,
+    options: String,
+}
+
+enum JobStatus {
+    Succeeded { outputs: Vec<PathBuf> },
+    Failed(JobError),
+    Cancelled,
+}
+
+struct FinishedJob {
+    name: String,
+    elapsed: Duration,
+    status: JobStatus,
+}
+
+/// 页面共用的后台任务，在单独的线程里处理，记录进度，支持取消，并捕获错误和 panic
+pub struct JobRunner<T> {
+    running: Option<RunningJob<T>>,
+    finished: Option<FinishedJob>,
+    run: Option<FinishedRun>,
+}
+
+impl<T> Default for JobRunner<T> {
+    fn default() -> Self {
+        JobRunner {
+            running: None,
+            finished: None,
+            run: None,
+        }
+    }
+}
+
+impl<T: JobOutputs + Send + 'static> JobRunner<T> {
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// 开始新的任务，已经有任务在运行时忽略
+    pub fn spawn<F>(&mut self, ctx: &egui::Context, name: impl Into<String>, f: F)
+    where
+        F: FnOnce(&Progress) -> core::Result<T> + Send + 'static,
+    {
+        if self.is_running() {
+            return;
+        }
+
+        // 日志窗口按任务名筛选，带上编号区分同一个工具的多次运行
+        let name = format!("{} #{}", name.into(), console::next_job_id());
+        let job_name = name.clone();
+        let (tx, rx) = mpsc::channel();
+        let progress = Progress::default();
+        let job_progress = progress.clone();
+        let ctx = ctx.clone();
+        let job = move || {
+            let _scope = JobScope::enter(&job_name);
+            let ret = match panic::catch_unwind(AssertUnwindSafe(|| f(&job_progress))) {
+                Ok(ret) => ret.map_err(JobError::Error),
+                Err(payload) => Err(JobError::Panic(panic_message(payload))),
+            };
+            tx.send(ret).ok();
+            ctx.request_repaint();
+        };
+        // 浏览器里没有线程，直接在这一帧里处理完，处理期间界面不会刷新
+        #[cfg(target_arch = "wasm32")]
+        job();
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::spawn(job);
+
+        self.running = Some(RunningJob {
+            name,
+            progress,
+            started: Instant::now(),
+            rx,
+            run: None,
+        });
+        self.finished = None;
+    }
+
+    /// 取走上一个结束的批量任务
+    pub fn take_run(&mut self) -> Option<FinishedRun> {
+        self.run.take()
+    }
+
+    /// 每帧调用，任务结束的那一帧返回结果，成功的值交给页面，失败时返回错误
+    pub fn poll(&mut self) -> Option<Result<T, &JobError>> {
+        let job = self.running.as_ref()?;
+        let ret = match job.rx.try_recv() {
+            Ok(ret) => ret,
+            Err(mpsc::TryRecvError::Empty) => return None,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                Err(JobError::Panic("job thread exited".to_string()))
+            }
+        };
+
+        let job = self.running.take()?;
+        let _scope = JobScope::enter(&job.name);
+        let elapsed = job.started.elapsed();
+        let mut success = false;
+        let mut outputs = Vec::new();
+        let (value, status) = match ret {
+            Ok(value) => {
+                outputs = value.outputs();
+                for output in &outputs {
+                    log::info!("{}: wrote {}", job.name, output.display());
+                }
+                success = value.succeeded();
+                // 取消的批量任务也把处理完的文件交给页面
+                let status = if value.cancelled() {
+                    JobStatus::Cancelled
+                } else {
+                    JobStatus::Succeeded {
+                        outputs: outputs.clone(),
+                    }
+                };
+                (Some(value), status)
+            }
+            Err(JobError::Error(core::Error::Cancelled)) => (None, JobStatus::Cancelled),
+            Err(e) => {
+                log::error!("{}: {}", job.name, e);
+                (None, JobStatus::Failed(e))
+            }
+        };
+        if let Some(RunInputs {
+            inputs,
+            roots,
+            options,
+        }) = job.run
+        {
+            self.run = Some(FinishedRun {
+                inputs,
+                roots,
+                options,
+                outputs,
+                duration: elapsed,
+                success,
+            });
+        }
+        let finished = self.finished.insert(FinishedJob {
+            name: job.name,
+            elapsed,
+            status,
+        });
+
+        match (value, &finished.status) {
+            (Some(value), _) => Some(Ok(value)),
+            (None, JobStatus::Failed(e)) => Some(Err(e)),
+            _ => None,
+        }
+    }
+
+    /// 显示当前任务的进度或者上一个任务的结果
+    pub fn status_bar(&mut self, ui: &mut egui::Ui) {
+        if let Some(job) = &self.running {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(&job.name);
+                let processed = job.progress.processed();
+                let total = job.progress.total();
+                if total > 0 {
+                    ui.add(
+                        egui::ProgressBar::new(processed as f32 / total as f32)
+                            .desired_width(160.0)
+                            .show_percentage(),
+                    );
+                }
+                ui.label(format!("{} KiB", processed / 1024));
+                if ui.button(tr!("job.cancel")).clicked() {
+                    job.progress.cancel();
+                }
+            });
+            ui.ctx().request_repaint_after(Duration::from_millis(100));
+        } else if let Some(job) = &self.finished {
+            ui.separator();
+            let elapsed = format!("{:.2}s", job.elapsed.as_secs_f32());
+            match &job.status {
+                JobStatus::Succeeded { outputs } => {
+                    ui.colored_label(
+                        egui::Color32::GREEN,
+                        tr!("job.succeeded", name = job.name, elapsed = elapsed),
+                    );
+                    for output in outputs {
+                        ui.horizontal(|ui| {
+                            ui.label("  →");
+                            output_label(ui, output);
+                        });
+                    }
+                }
+                JobStatus::Failed(e) => {
+                    ui.colored_label(
+                        ui.visuals().error_fg_color,
+                        tr!(
+                            "job.failed",
+                            name = job.name,
+                            elapsed = elapsed,
+                            error = e.message()
+                        ),
+                    );
+                }
+                JobStatus::Cancelled => {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        tr!("job.cancelled", name = job.name, elapsed = elapsed),
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl<T: JobOutputs + Send + 'static> JobRunner<BatchResult<T>> {
+    /// 依次处理每个文件，单个文件失败或 panic 不影响后面的文件
+    ///
+    /// 取消时不再处理剩下的文件，它们记为 [`core::Error::Cancelled`]，已经处理完的结果照常返回
+    ///
+    /// `options` 是页面序列化的处理选项，和输入一起记录到历史里
+    pub fn spawn_batch<F>(
+        &mut self,
+        ctx: &egui::Context,
+        name: impl Into<String>,
+        files: Vec<PathBuf>,
+        options: String,
+        f: F,
+    ) where
+        F: Fn(&Path, &Progress) -> core::Result<T> + Send + 'static,
+    {
+        self.spawn_batch_passes(ctx, name, files, options, 1, f);
+    }
+
+    /// 和 [`Self::spawn_batch`] 一样，但是每个文件要完整读 `passes` 遍，
+    /// 比如先写 utf8 副本再转换，进度的总量按 `passes` 倍的文件大小算
+    pub fn spawn_batch_passes<F>(
+        &mut self,
+        ctx: &egui::Context,
+        name: impl Into<String>,
+        files: Vec<PathBuf>,
+        options: String,
+        passes: u64,
+        f: F,
+    ) where
+        F: Fn(&Path, &Progress) -> core::Result<T> + Send + 'static,
+    {
+        if self.is_running() {
+            return;
+        }
+        let name = tr!("job.batch", name = name.into(), count = files.len());
+        let inputs = files.clone();
+        self.spawn(ctx, name, move |progress| {
+            let total: u64 = files.iter().map(|file| core::fs::file_len(file)).sum();
+            progress.set_total(total * passes);
+            let mut results = Vec::with_capacity(files.len());
+            for file in files {
+                if progress.is_cancelled() {
+                    results.push((file, Err(JobError::Error(core::Error::Cancelled))));
+                    continue;
+                }
+                let ret = match panic::catch_unwind(AssertUnwindSafe(|| f(&file, progress))) {
+                    Ok(ret) => ret.map_err(JobError::Error),
+                    Err(payload) => Err(JobError::Panic(panic_message(payload))),
+                };
+                match &ret {
+                    Ok(_) => log::info!("{}: done", file.display()),
+                    Err(JobError::Error(core::Error::Cancelled)) => {}
+                    Err(e) => log::error!("{}: {}", file.display(), e),
+                }
+                results.push((file, ret));
+            }
+            Ok(BatchResult { files: results })
+        });
+        if let Some(job) = &mut self.running {
+            job.run = Some(RunInputs {
+                inputs,
+                roots: Vec::new(),
+                options,
+            });
+        }
+    }
+
+    /// 输入是从文件夹展开的文件时，记下列表里原来的文件和文件夹，重新运行时用它们
+    pub fn set_roots(&mut self, roots: Vec<PathBuf>) {
+        if let Some(RunningJob { run: Some(run), .. }) = &mut self.running {
+            run.roots = roots;
+        }
+    }
+}
@@ -1,55 +1,18 @@
-use serde::Deserialize;
-use std::{
-    fs::File,
-    io::{BufRead, BufReader, Lines, Write},
-    sync::mpsc::{self, Receiver, Sender},
-    thread,
-};
+use std::path::PathBuf;
 
-use super::{preview_files_being_dropped, show_page_header};
-use crate::{add_drop_file, component::Interface};
+use super::{
+    load_settings, output_policy_ui, preview_files_being_dropped, show_page_header, BatchResult,
+    FileQueue, FinishedRun, FolderWatch, JobRunner, Settings,
+};
+use crate::add_drop_file;
+use crate::component::{Interface, ToolInfo};
+use crate::core::logic::{self, LogicIISArgs, LogicSpiArgs, LogicUARTArgs, SpiConvType};
+use crate::core::OutputPolicy;
+use crate::tr;
 
 static LOGIC_TOOL_PAGE_KEY: &str = "LogicKey";
 
-#[derive(Copy, Clone, PartialEq, Debug, Deserialize)]
-enum SpiConvType {
-    RAW,
-    BluetrumVoiceDump,
-    TXT,
-}
-
-#[derive(Copy, Clone, PartialEq, Debug)]
-struct LogicSpiArgs {
-    conv_type: SpiConvType,
-}
-
-impl Default for LogicSpiArgs {
-    fn default() -> Self {
-        LogicSpiArgs {
-            conv_type: SpiConvType::RAW,
-        }
-    }
-}
-
-#[derive(Clone, Copy, PartialEq, Debug)]
-struct LogicIISArgs {}
-
-impl Default for LogicIISArgs {
-    fn default() -> Self {
-        LogicIISArgs {}
-    }
-}
-
-#[derive(Clone, Copy, PartialEq, Debug)]
-struct LogicUARTArgs {}
-
-impl Default for LogicUARTArgs {
-    fn default() -> Self {
-        LogicUARTArgs {}
-    }
-}
-
-#[derive(PartialEq, Debug, Clone, Default)]
+#[derive(PartialEq, Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
 enum Protocal {
     #[default]
     SPI,
@@ -57,7 +20,8 @@ enum Protocal {
     UART,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 struct ProtocalArgs {
     spi: LogicSpiArgs,
     iis: LogicIISArgs,
@@ -65,50 +29,76 @@ struct ProtocalArgs {
 }
 
 #[derive(Debug, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 struct LogicToolPageSave {
-    visable: bool,
+    version: u32,
+    protocal: Protocal,
+    arg: ProtocalArgs,
+    output: OutputPolicy,
+    /// 上次列表里的文件
+    inputs: Vec<PathBuf>,
+    recent: Vec<PathBuf>,
+    watch_dir: String,
+    watching: bool,
 }
 
-#[derive(Debug)]
-pub struct LogicToolPage {
-    save: LogicToolPageSave,
+/// 历史记录里保存的处理选项
+#[derive(serde::Deserialize, serde::Serialize)]
+struct RunOptions {
     protocal: Protocal,
-    path: String,
-    history: Option<String>,
-    doing: bool,
-    channel: (Sender<bool>, Receiver<bool>),
     arg: ProtocalArgs,
+    output: OutputPolicy,
+}
+
+impl Settings for LogicToolPageSave {
+    /// 1: 保存协议和参数，0 只有没用到的 `visable`
+    const VERSION: u32 = 1;
+
+    fn version(&mut self) -> &mut u32 {
+        &mut self.version
+    }
+}
+
+pub struct LogicToolPage {
+    save: LogicToolPageSave,
+    files: FileQueue,
+    history: Option<Vec<PathBuf>>,
+    job: JobRunner<BatchResult<Vec<PathBuf>>>,
+    watch: FolderWatch,
 }
 
 add_drop_file!(LogicToolPage);
 
 impl eframe::App for LogicToolPage {
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.save.inputs = self.files.paths();
+        self.save.recent = self.files.recent().to_vec();
         eframe::set_value(storage, LOGIC_TOOL_PAGE_KEY, &self.save);
     }
     fn update(&mut self, _ctx: &egui::Context, _frame: &mut eframe::Frame) {}
 }
 
 impl Interface for LogicToolPage {
+    fn info() -> ToolInfo {
+        ToolInfo {
+            id: "logic",
+            name: "logic.name",
+            description: "logic.description",
+            icon: "📈",
+        }
+    }
     fn new(cc: &eframe::CreationContext<'_>) -> Self {
         let mut page = LogicToolPage {
-            save: LogicToolPageSave { visable: false },
-            protocal: Protocal::SPI,
-            path: String::new(),
+            save: load_settings(cc.storage, LOGIC_TOOL_PAGE_KEY),
+            files: FileQueue::new(&["csv"]),
             history: None,
-            doing: false,
-            channel: mpsc::channel(),
-            arg: ProtocalArgs {
-                spi: LogicSpiArgs {
-                    conv_type: SpiConvType::RAW,
-                },
-                iis: LogicIISArgs::default(),
-                uart: LogicUARTArgs::default(),
-            },
+            job: JobRunner::default(),
+            watch: FolderWatch::new(&["csv"], logic::OUTPUT_FORMATS),
         };
 
-        if let Some(storage) = cc.storage {
-            page.save = eframe::get_value(storage, LOGIC_TOOL_PAGE_KEY).unwrap_or_default();
+        page.files.restore(&page.save.inputs, &page.save.recent);
+        if page.save.watching {
+            page.watch.start(&cc.egui_ctx, &page.save.watch_dir);
         }
         page
     }
@@ -120,311 +110,135 @@ impl Interface for LogicToolPage {
     ) {
         show_page_header(ui, close);
 
-        ui.heading("Logic Tool");
+        ui.heading(tr!("logic.name"));
 
-        egui::Grid::new("hardfault")
+        egui::Grid::new("logic")
             .num_columns(2)
             .spacing([40.0, 4.0])
             .striped(true)
             .show(ui, |ui| self.grid_contents(ctx, ui));
 
-        if let Ok(doing) = self.channel.1.try_recv() {
-            self.doing = doing;
-        }
+        self.job.status_bar(ui);
 
         self.get_drop_file(ctx, ui);
     }
-}
-
-impl LogicToolPage {
-    fn grid_contents(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) {
-        ui.label("协议类型");
-        ui.horizontal(|ui| {
-            ui.radio_value(&mut self.protocal, Protocal::SPI, "SPI");
-            ui.radio_value(&mut self.protocal, Protocal::IIS, "IIS");
-            ui.radio_value(&mut self.protocal, Protocal::UART, "UART");
-        });
-        ui.end_row();
 
-        ui.label("文件地址");
-        ui.text_edit_singleline(&mut self.path);
-        ui.end_row();
-
-        match &mut self.protocal {
-            Protocal::SPI => {
-                ui.label("spi 格式");
-                ui.horizontal(|ui| {
-                    ui.radio_value(&mut self.arg.spi.conv_type, SpiConvType::RAW, "RAW");
-                    ui.radio_value(
-                        &mut self.arg.spi.conv_type,
-                        SpiConvType::BluetrumVoiceDump,
-                        "蓝讯音频 DUMP 格式",
-                    );
-                    ui.radio_value(&mut self.arg.spi.conv_type, SpiConvType::TXT, "TXT");
-                });
-                ui.end_row();
-                ui.add_enabled_ui(!self.doing && self.path.len() > 0, |ui| {
-                    if ui.button("处理").clicked() {
-                        self.doing = true;
-                        let tx = self.channel.0.clone();
-                        let path = self.path.clone();
-                        let arg;
-                        arg = self.arg.spi.clone();
-                        thread::spawn(move || {
-                            logic_tool_proc_spi(&arg, &path);
-                            tx.send(false).unwrap();
-                        });
-                    }
-                });
-                ui.end_row();
-            }
-            Protocal::IIS => {
-                ui.add_enabled_ui(!self.doing && self.path.len() > 0, |ui| {
-                    if ui.button("处理").clicked() {
-                        self.doing = true;
-                        let tx = self.channel.0.clone();
-                        let path = self.path.clone();
-                        let arg = self.arg.iis.clone();
-                        thread::spawn(move || {
-                            logic_tool_proc_iis(&arg, &path);
-                            tx.send(false).unwrap();
-                        });
-                    }
-                });
-                ui.end_row();
-            }
-            Protocal::UART => {
-                ui.add_enabled_ui(!self.doing && self.path.len() > 0, |ui| {
-                    if ui.button("处理").clicked() {
-                        self.doing = true;
-                        let tx = self.channel.0.clone();
-                        let path = self.path.clone();
-                        let arg = self.arg.uart.clone();
-                        thread::spawn(move || {
-                            logic_tool_proc_uart(&arg, &path);
-                            tx.send(false).unwrap();
-                        });
-                    }
-                });
-                ui.end_row();
-            }
+    fn tick(&mut self, ctx: &egui::Context) {
+        if let Some(Ok(batch)) = self.job.poll() {
+            self.files.set_results(&batch);
         }
-    }
-}
-
-fn logic_tool_preproc(in_file: &str, header: &str) -> Option<Lines<BufReader<File>>> {
-    if let Ok(file) = File::open(&in_file) {
-        let mut lines = BufReader::new(file).lines();
-        let first = lines.next();
-        if let Some(first) = first {
-            if first.unwrap().contains(header) {
-                return Some(lines);
-            }
+        if let Some(paths) = self.watch.poll(&mut self.files, self.job.is_running()) {
+            self.start(ctx, paths);
         }
     }
-    return None;
-}
-
-const KINGST_ERROR_STR: &str =
-    "The initial (idle) state of the CLK line does not match the settings";
-
-const KINGST_IIS_FILE_FORMAT: &str = "Time [s],Channel,Value";
-fn logic_tool_proc_iis(_args: &LogicIISArgs, path: &str) {
-    let conv_file = path;
 
-    if let Some(src) = logic_tool_preproc(&conv_file, KINGST_IIS_FILE_FORMAT) {
-        // println!("open {} success", &conv_file);
-        let out_path = format!("{}.out", &conv_file);
-        let mut out_file = File::create(out_path).unwrap();
-
-        for line in src {
-            if let Ok(line) = line {
-                // 跳过错误数据
-                if line.contains(KINGST_ERROR_STR) {
-                    continue;
-                }
-
-                let data: String = line.split(',').filter(|w| w.contains("0x")).collect();
-                let data = data.trim_start_matches("0x");
-                let data = u16::from_str_radix(data, 16).unwrap();
-                out_file.write(&data.to_le_bytes()).unwrap();
-            }
-        }
+    fn take_run(&mut self) -> Option<FinishedRun> {
+        self.job.take_run()
     }
-}
-
-const KINGST_SPI_FILE_FORMAT: &str = "Time [s],Packet ID,MOSI,MISO";
 
-fn logic_tool_proc_spi_raw(conv_file: &str) {
-    if let Some(src) = logic_tool_preproc(conv_file, KINGST_SPI_FILE_FORMAT) {
-        // println!("open {} success", conv_file);
-        let out_path = format!("{}.out", conv_file);
-        let mut out_file = File::create(out_path).unwrap();
-
-        for line in src {
-            if let Ok(line) = line {
-                // 跳过错误数据
-                if line.contains(KINGST_ERROR_STR) {
-                    continue;
-                }
-
-                let data: String = line.split(',').filter(|w| w.contains("0x")).collect();
-                let data = data.trim_start_matches("0x");
-                let data = u8::from_str_radix(data, 16).unwrap();
-                out_file.write(&[data]).unwrap();
-            }
-        }
+    fn inputs(&self) -> Vec<PathBuf> {
+        self.files.paths()
     }
-}
-
-#[derive(Debug, Default)]
-struct BluetrumVoiceDump {
-    version: [u8; 4],
-    frame_type: u8,
-    len: u16,
-    frame_num: u8,
-}
-
-enum BluetrumVoiceDumpState {
-    Header,
-    Body,
-}
-
-fn logic_tool_proc_spi_bluetrum(conv_file: &str) {
-    if let Some(src) = logic_tool_preproc(conv_file, KINGST_SPI_FILE_FORMAT) {
-        let out1_path = format!("{}.out1", conv_file);
-        let out2_path = format!("{}.out2", conv_file);
-        let out3_path = format!("{}.out3", conv_file);
-
-        let mut out1_file = File::create(out1_path).unwrap();
-        let mut out2_file = File::create(out2_path).unwrap();
-        let mut out3_file = File::create(out3_path).unwrap();
-
-        let mut cnt = 0;
-        let mut header = BluetrumVoiceDump::default();
-        let mut header_cache = Vec::new();
-        let mut state = BluetrumVoiceDumpState::Header;
-        for line in src {
-            if let Ok(line) = line {
-                // 跳过错误数据
-                if line.contains(KINGST_ERROR_STR) {
-                    continue;
-                }
 
-                let data: String = line.split(',').filter(|w| w.contains("0x")).collect();
-                let data = data.trim_start_matches("0x");
-                let data = u8::from_str_radix(data, 16).unwrap();
-                // out_file.write(&[data]).unwrap();
-                // println!("line:{} - {:x}", line, data);
+    fn options(&self) -> String {
+        serde_json::to_string(&RunOptions {
+            protocal: self.save.protocal.clone(),
+            arg: self.save.arg.clone(),
+            output: self.save.output.clone(),
+        })
+        .unwrap_or_default()
+    }
 
-                match state {
-                    BluetrumVoiceDumpState::Header => {
-                        header_cache.push(data);
-                        if header_cache.len() == 8 {
-                            state = BluetrumVoiceDumpState::Body;
-                            header = BluetrumVoiceDump {
-                                version: header_cache[0..4].try_into().unwrap(),
-                                frame_type: header_cache[4],
-                                len: (header_cache[5] as u16) << 8 | (header_cache[6] as u16),
-                                // len: u16::from_be_bytes(header_cache[5..6].try_into().unwrap()),
-                                frame_num: header_cache[7],
-                            };
-                            println!("{:?} {}", header.version, header.frame_num);
-                        }
-                    }
-                    BluetrumVoiceDumpState::Body => {
-                        match header.frame_type {
-                            0 => {
-                                out1_file.write(&[data]).unwrap();
-                            }
-                            2 => {
-                                out2_file.write(&[data]).unwrap();
-                            }
-                            4 => {
-                                out3_file.write(&[data]).unwrap();
-                            }
-                            _ => {}
-                        };
+    fn apply(&mut self, inputs: Vec<PathBuf>, options: &str) -> serde_json::Result<()> {
+        let options: RunOptions = serde_json::from_str(options)?;
+        self.save.protocal = options.protocal;
+        self.save.arg = options.arg;
+        self.save.output = options.output;
+        self.files.replace(inputs);
+        Ok(())
+    }
 
-                        cnt += 1;
-                        if cnt == header.len {
-                            state = BluetrumVoiceDumpState::Header;
-                            header_cache.clear();
-                            cnt = 0;
-                        }
-                    }
-                }
-            }
-        }
+    fn run(&mut self, ctx: &egui::Context) {
+        self.start(ctx, self.files.paths());
     }
 }
 
-fn logic_tool_proc_spi_txt(conv_file: &str) {
-    if let Some(src) = logic_tool_preproc(conv_file, KINGST_SPI_FILE_FORMAT) {
-        // println!("open {} success", conv_file);
-        let out_path = format!("{}.txt", conv_file);
-        let mut out_file = File::create(out_path).unwrap();
-
-        let mut cnt = 0;
-        for line in src {
-            if let Ok(line) = line {
-                // 跳过错误数据
-                if line.contains(KINGST_ERROR_STR) {
-                    continue;
-                }
-
-                let data: String = line.split(',').filter(|w| w.contains("0x")).collect();
-                let data = data.trim_start_matches("0x");
-                let data = u8::from_str_radix(data, 16).unwrap();
-                // out_file.write(&[data]).unwrap();
-                if cnt > 0 && (cnt % 16 == 0) {
-                    write!(out_file, "\n").unwrap();
-                }
-                write!(out_file, "{:02x} ", data).unwrap();
-                cnt += 1;
+impl LogicToolPage {
+    /// 用当前选择的协议和参数处理文件
+    fn start(&mut self, ctx: &egui::Context, paths: Vec<PathBuf>) {
+        let options = self.options();
+        match self.save.protocal {
+            Protocal::SPI => {
+                let arg = self.save.arg.spi;
+                let output = self.save.output.clone();
+                self.job.spawn_batch(
+                    ctx,
+                    tr!("logic.job", protocol = "SPI"),
+                    paths,
+                    options,
+                    move |path, progress| logic::spi_file(&arg, path, &output, progress),
+                );
+            }
+            Protocal::IIS => {
+                let arg = self.save.arg.iis;
+                let output = self.save.output.clone();
+                self.job.spawn_batch(
+                    ctx,
+                    tr!("logic.job", protocol = "IIS"),
+                    paths,
+                    options,
+                    move |path, progress| logic::iis_file(&arg, path, &output, progress),
+                );
+            }
+            Protocal::UART => {
+                let arg = self.save.arg.uart;
+                let output = self.save.output.clone();
+                self.job.spawn_batch(
+                    ctx,
+                    tr!("logic.job", protocol = "UART"),
+                    paths,
+                    options,
+                    move |path, progress| logic::uart_file(&arg, path, &output, progress),
+                );
             }
         }
     }
-}
 
-fn logic_tool_proc_spi(args: &LogicSpiArgs, path: &str) {
-    match args.conv_type {
-        SpiConvType::RAW => logic_tool_proc_spi_raw(path),
-        SpiConvType::BluetrumVoiceDump => logic_tool_proc_spi_bluetrum(path),
-        SpiConvType::TXT => logic_tool_proc_spi_txt(path),
-    }
-}
+    fn grid_contents(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        ui.label(tr!("logic.protocol"));
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.save.protocal, Protocal::SPI, "SPI");
+            ui.radio_value(&mut self.save.protocal, Protocal::IIS, "IIS");
+            ui.radio_value(&mut self.save.protocal, Protocal::UART, "UART");
+        });
+        ui.end_row();
 
-const KINGST_UART_FILE_FORMAT: &str = "Time [s],Value,Parity Error,Framing Error";
+        self.files.ui(ui);
+
+        if self.save.protocal == Protocal::SPI {
+            ui.label(tr!("logic.spi_format"));
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.save.arg.spi.conv_type, SpiConvType::RAW, "RAW");
+                ui.radio_value(
+                    &mut self.save.arg.spi.conv_type,
+                    SpiConvType::BluetrumVoiceDump,
+                    tr!("logic.bluetrum_dump"),
+                );
+                ui.radio_value(&mut self.save.arg.spi.conv_type, SpiConvType::TXT, "TXT");
+            });
+            ui.end_row();
+        }
 
-fn logic_tool_proc_uart_txt(conv_file: &str) {
-    if let Some(src) = logic_tool_preproc(conv_file, KINGST_UART_FILE_FORMAT) {
-        // println!("open {} success", conv_file);
-        let out_path = format!("{}.txt", conv_file);
-        let mut out_file = File::create(out_path).unwrap();
+        output_policy_ui(ui, &mut self.save.output);
 
-        let mut cnt = 0;
-        for line in src {
-            if let Ok(line) = line {
-                // 跳过错误数据
-                if line.contains(KINGST_ERROR_STR) {
-                    continue;
-                }
+        self.watch
+            .ui(ui, &mut self.save.watch_dir, &mut self.save.watching);
 
-                let data: String = line.split(',').filter(|w| w.contains("0x")).collect();
-                let data = data.trim_start_matches("0x");
-                let data = u8::from_str_radix(data, 16).unwrap();
-                // out_file.write(&[data]).unwrap();
-                if cnt > 0 && (cnt % 16 == 0) {
-                    write!(out_file, "\n").unwrap();
-                }
-                write!(out_file, "{:02x} ", data).unwrap();
-                cnt += 1;
+        ui.add_enabled_ui(!self.job.is_running() && !self.files.is_empty(), |ui| {
+            if ui.button(tr!("common.process")).clicked() {
+                self.start(ctx, self.files.paths());
             }
-        }
+        });
+        ui.end_row();
     }
 }
-
-fn logic_tool_proc_uart(_args: &LogicUARTArgs, path: &str) {
-    logic_tool_proc_uart_txt(path);
-}
@@ -1,21 +1,86 @@
+#[cfg(feature = "analyze")]
+mod analyze_tool;
+#[cfg(feature = "convert")]
+mod convert_tool;
+mod dialog;
+#[cfg(feature = "hardfault")]
 mod hardfault_tool;
+#[cfg(feature = "hci")]
 mod hci_tool;
+#[cfg(any(feature = "hardfault", feature = "hci", feature = "convert"))]
+mod inspector;
+#[cfg(any_tool)]
+mod job;
+#[cfg(feature = "logic")]
 mod logic_tool;
-mod analyze_tool;
-
-use std::{
-    fs::{self, File},
-    io::Write,
-    path::PathBuf,
-};
-
-pub use hardfault_tool::HardfaultToolPage;
-pub use hci_tool::HciToolPage;
-pub use logic_tool::LogicToolPage;
-pub use analyze_tool::AnalyzeToolPage;
+#[cfg(any_tool)]
+mod page;
+#[cfg(any_tool)]
+mod queue;
+#[cfg(any(
+    feature = "logic",
+    feature = "hardfault",
+    feature = "hci",
+    feature = "analyze"
+))]
+mod watch;
+#[cfg(target_arch = "wasm32")]
+pub mod web;
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub use dialog::{path_edit, Browse};
+#[cfg(any_tool)]
+pub use job::{BatchResult, JobRunner};
+#[cfg(any_tool)]
+pub use page::{output_label, output_policy_ui, preview_files_being_dropped, show_page_header};
+#[cfg(any_tool)]
+pub use queue::FileQueue;
+#[cfg(any(
+    feature = "logic",
+    feature = "hardfault",
+    feature = "hci",
+    feature = "analyze"
+))]
+pub use watch::FolderWatch;
+
+#[cfg(feature = "analyze")]
+use analyze_tool::AnalyzeToolPage;
+#[cfg(feature = "convert")]
+use convert_tool::ConvertToolPage;
+#[cfg(feature = "hardfault")]
+use hardfault_tool::HardfaultToolPage;
+#[cfg(feature = "hci")]
+use hci_tool::HciToolPage;
+#[cfg(feature = "logic")]
+use logic_tool::LogicToolPage;
+
+#[cfg(any(feature = "hardfault", feature = "hci", feature = "convert"))]
+pub use crate::core::encoding::FileEncoding;
+#[cfg(any(feature = "hardfault", feature = "hci", feature = "convert"))]
+pub use inspector::EncodingInspector;
+#[cfg(any(feature = "hardfault", feature = "hci", feature = "convert"))]
+pub use page::file_encoding_select;
+
+/// 工具页面的描述，首页宫格、导航和保存都用它
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToolInfo {
+    /// 唯一的 id，会保存到 storage 里，不要修改已有的 id
+    pub id: &'static str,
+    /// 翻译词条的 key，显示时用 `tr!`
+    pub name: &'static str,
+    /// 翻译词条的 key，显示时用 `tr!`
+    pub description: &'static str,
+    /// 首页按钮上显示的 emoji
+    pub icon: &'static str,
+}
 
 pub trait Interface: eframe::App {
     fn new(cc: &eframe::CreationContext<'_>) -> Self
+    where
+        Self: Sized;
+    fn info() -> ToolInfo
     where
         Self: Sized;
     fn new_update<'a>(
@@ -24,6 +89,98 @@ pub trait Interface: eframe::App {
         ctx: &egui::Context,
         close: Box<dyn FnMut() + 'a>,
     );
+
+    /// 每帧都会调用，不管页面是否显示：收取后台任务的结果，处理监控文件夹里的新文件
+    fn tick(&mut self, ctx: &egui::Context);
+
+    /// 取走刚结束的批量任务，app 会记录到历史里
+    fn take_run(&mut self) -> Option<FinishedRun>;
+
+    /// 列表里的文件
+    fn inputs(&self) -> Vec<PathBuf>;
+
+    /// 当前的处理选项，json 格式，和 [`FinishedRun::options`] 一样
+    fn options(&self) -> String;
+
+    /// 换成 `inputs` 和 `options`，选项格式不对时返回错误并保持不变
+    fn apply(&mut self, inputs: Vec<PathBuf>, options: &str) -> serde_json::Result<()>;
+
+    /// 处理列表里的所有文件
+    fn run(&mut self, ctx: &egui::Context);
+
+    /// 用历史记录里的输入和选项重新运行
+    fn rerun(&mut self, ctx: &egui::Context, inputs: Vec<PathBuf>, options: &str) {
+        match self.apply(inputs, options) {
+            Ok(()) => self.run(ctx),
+            Err(e) => log::warn!("invalid options {}: {}", options, e),
+        }
+    }
+}
+
+/// 页面保存到 storage 里的设置
+///
+/// 新增字段用 `#[serde(default)]` 就可以读取旧数据；字段改名或含义变化时增加
+/// `VERSION`，在 [`Settings::migrate`] 里转换旧版本的数据。
+pub trait Settings: Default + serde::Serialize + serde::de::DeserializeOwned {
+    const VERSION: u32;
+
+    fn version(&mut self) -> &mut u32;
+
+    /// 把 `from` 版本的数据升级到 `VERSION`，默认什么都不做
+    fn migrate(&mut self, _from: u32) {}
+}
+
+/// 读取页面设置，没有保存过时返回默认值
+pub fn load_settings<T: Settings>(storage: Option<&dyn eframe::Storage>, key: &str) -> T {
+    let Some(mut settings) = storage.and_then(|storage| eframe::get_value::<T>(storage, key))
+    else {
+        let mut settings = T::default();
+        *settings.version() = T::VERSION;
+        return settings;
+    };
+    let from = *settings.version();
+    if from < T::VERSION {
+        log::info!("migrate {} settings from version {}", key, from);
+        settings.migrate(from);
+        *settings.version() = T::VERSION;
+    }
+    settings
+}
+
+/// 注册表里的一项，新增工具时在 [`registry`] 里加一行
+pub struct ToolEntry {
+    pub info: ToolInfo,
+    pub create: fn(&eframe::CreationContext<'_>) -> Box<dyn Interface>,
+}
+
+// 一个工具都不编译时注册表是空的
+#[cfg_attr(not(any_tool), allow(dead_code))]
+impl ToolEntry {
+    pub fn of<T: Interface + 'static>() -> Self {
+        fn create<T: Interface + 'static>(cc: &eframe::CreationContext<'_>) -> Box<dyn Interface> {
+            Box::new(T::new(cc))
+        }
+        ToolEntry {
+            info: T::info(),
+            create: create::<T>,
+        }
+    }
+}
+
+/// 所有编译进来的工具，首页按这里的顺序排列
+pub fn registry() -> Vec<ToolEntry> {
+    vec![
+        #[cfg(feature = "logic")]
+        ToolEntry::of::<LogicToolPage>(),
+        #[cfg(feature = "hardfault")]
+        ToolEntry::of::<HardfaultToolPage>(),
+        #[cfg(feature = "hci")]
+        ToolEntry::of::<HciToolPage>(),
+        #[cfg(feature = "analyze")]
+        ToolEntry::of::<AnalyzeToolPage>(),
+        #[cfg(feature = "convert")]
+        ToolEntry::of::<ConvertToolPage>(),
+    ]
 }
 
 #[macro_export]
@@ -35,141 +192,50 @@ macro_rules! add_drop_file {
             fn get_drop_file(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
                 ctx.input(|i| {
                     if let Some(point) = i.pointer.latest_pos() {
-                        if let Some(path) = &self.history {
+                        if let Some(paths) = self.history.take() {
                             if ui.min_rect().contains(point) {
-                                self.path = path.to_string();
+                                self.files.add(paths);
                             }
-                            self.history = None;
                         }
                     }
                 });
 
-                if let Some(path) = preview_files_being_dropped(ctx) {
-                    self.history = Some(path);
+                if let Some(paths) = preview_files_being_dropped(ctx) {
+                    self.history = Some(paths);
                     ctx.request_repaint();
                 }
-            }
-        }
-    };
-}
 
-pub fn show_page_header<'a>(ui: &mut egui::Ui, mut close: Box<dyn FnMut() + 'a>) {
-    if ui.button("返回").clicked() {
-        close();
-    }
-    ui.separator();
-}
-
-/// Preview hovering files:
-pub fn preview_files_being_dropped(ctx: &egui::Context) -> Option<String> {
-    use egui::*;
-    use std::fmt::Write as _;
-
-    if !ctx.input(|i| i.raw.hovered_files.is_empty()) {
-        let path = ctx.input(|i| {
-            let mut res = String::new();
-            for file in &i.raw.hovered_files {
-                if let Some(path) = &file.path {
-                    write!(res, "{}", path.display()).ok();
-                } else if !file.mime.is_empty() {
-                    write!(res, "{}", file.mime).ok();
-                } else {
-                    res += "???";
-                }
+                // 网页版拖动时拿不到鼠标位置，交给所有显示着的、支持这种文件的页面
+                #[cfg(target_arch = "wasm32")]
+                self.files.add(
+                    $crate::component::web::dropped_files(ctx)
+                        .into_iter()
+                        .filter(|path| {
+                            $crate::component::has_extension(path, self.files.extensions)
+                        }),
+                );
             }
-            res
-        });
-        let text = format!("Dropping files:\n\n{}", path);
-
-        let painter =
-            ctx.layer_painter(LayerId::new(Order::Foreground, Id::new("file_drop_target")));
-
-        let screen_rect = ctx.screen_rect();
-        painter.rect_filled(screen_rect, 0.0, Color32::from_black_alpha(192));
-        painter.text(
-            screen_rect.center(),
-            Align2::CENTER_CENTER,
-            text,
-            TextStyle::Heading.resolve(&ctx.style()),
-            Color32::WHITE,
-        );
-        // ctx.copy_text(path);
-        return Some(path);
-    }
-    None
-}
-
-#[derive(Debug, PartialEq, Clone)]
-pub enum FileEncoding {
-    UTF8,
-    UTF16,
-    UTF32,
-    GBK,
-    Other,
-}
-
-fn detect_encoding(path: &str) -> Option<String> {
-    if let Ok(result) = charset_normalizer_rs::from_path(&PathBuf::from(path), None) {
-        if let Some(best) = result.get_best() {
-            return Some(best.encoding().to_uppercase().to_string());
         }
-    }
-    None
-}
-
-fn convert_file_to_utf8(path: &str, encoding_name: &str) -> std::io::Result<()> {
-    use std::io::Read;
-
-    let mut file = File::open(path)?;
-    let output_path = format!("{}.tmp", path);
-
-    let mut buf = Vec::new();
-    file.read_to_end(&mut buf)?;
-    println!("{} {}", buf.len(), encoding_name);
-    let encoding =
-        encoding_rs::Encoding::for_label(encoding_name.as_bytes()).unwrap_or(encoding_rs::UTF_8);
-
-    // 将字节向量解码为UTF-8
-    let (decoded_str, _, had_errors) = encoding.decode(&buf);
-
-    if had_errors {
-        eprintln!("Warning: Some characters could not be decoded correctly.");
-    }
-
-    // 打开输出文件
-    let mut output_file = File::create(&output_path)?;
-
-    // 将解码后的字符串写入输出文件
-    output_file.write_all(decoded_str.as_bytes())?;
-
-    fs::rename(path, &format!("{}.bak", path))?;
-    fs::rename(&output_path, path)?;
-    Ok(())
+    };
 }
 
-pub fn file_encoding_select(ui: &mut egui::Ui, encode: &mut FileEncoding) {
-    ui.label("选择输入文件编码");
-    egui::ComboBox::from_label("文件编码")
-        .selected_text(format!("{:?}", encode))
-        .show_ui(ui, |ui| {
-            ui.selectable_value(encode, FileEncoding::UTF8, "UTF8");
-            ui.selectable_value(encode, FileEncoding::UTF16, "UTF16");
-            ui.selectable_value(encode, FileEncoding::UTF32, "UTF32");
-            ui.selectable_value(encode, FileEncoding::GBK, "GBK");
-            ui.selectable_value(encode, FileEncoding::Other, "Other");
-        });
-    ui.end_row();
+/// 结束的批量任务
+pub struct FinishedRun {
+    pub inputs: Vec<PathBuf>,
+    /// 页面列表里的文件和文件夹，和 `inputs` 一样时为空，见 [`JobRunner::set_roots`]
+    pub roots: Vec<PathBuf>,
+    /// 页面序列化的处理选项，重新运行时原样交回页面
+    pub options: String,
+    pub outputs: Vec<PathBuf>,
+    pub duration: Duration,
+    pub success: bool,
 }
 
-pub fn file_encoding_proc(path: &str, encode: &FileEncoding) {
-    let encode = match encode {
-        FileEncoding::UTF8 => return,
-        FileEncoding::UTF16 => Some("UTF16".to_string()),
-        FileEncoding::UTF32 => Some("UTF32".to_string()),
-        FileEncoding::GBK => Some("GBK".to_string()),
-        _ => detect_encoding(path),
-    };
-    if let Some(encode) = encode {
-        convert_file_to_utf8(path, &encode).unwrap();
-    }
+/// 扩展名不区分大小写，`extensions` 为空时匹配所有文件
+pub fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    extensions.is_empty()
+        || path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
 }
@@ -0,0 +1,140 @@
+//! 工具页面共用的界面部件
+
+use std::path::{Path, PathBuf};
+
+#[cfg(target_arch = "wasm32")]
+use super::web;
+use super::{path_edit, Browse};
+#[cfg(any(feature = "hardfault", feature = "hci", feature = "convert"))]
+use super::{EncodingInspector, FileEncoding};
+use crate::core::output::DEFAULT_TEMPLATE;
+use crate::core::{Conflict, OutputPolicy};
+use crate::tr;
+
+/// 输出文件，网页版点击后下载
+pub fn output_label(ui: &mut egui::Ui, path: &Path) {
+    #[cfg(target_arch = "wasm32")]
+    if ui.link(format!("⬇ {}", path.display())).clicked() {
+        web::download(path);
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    ui.label(path.display().to_string());
+}
+
+pub fn show_page_header<'a>(ui: &mut egui::Ui, mut close: Box<dyn FnMut() + 'a>) {
+    if ui.button(tr!("common.close")).clicked() {
+        close();
+    }
+    ui.separator();
+}
+
+/// Preview hovering files, returns the paths of all hovered files:
+pub fn preview_files_being_dropped(ctx: &egui::Context) -> Option<Vec<PathBuf>> {
+    use egui::*;
+    use std::fmt::Write as _;
+
+    if !ctx.input(|i| i.raw.hovered_files.is_empty()) {
+        let (paths, text) = ctx.input(|i| {
+            let mut paths = Vec::new();
+            let mut res = String::new();
+            for file in &i.raw.hovered_files {
+                if let Some(path) = &file.path {
+                    writeln!(res, "{}", path.display()).ok();
+                    paths.push(path.clone());
+                } else if !file.mime.is_empty() {
+                    writeln!(res, "{}", file.mime).ok();
+                } else {
+                    res += "???\n";
+                }
+            }
+            (paths, res)
+        });
+        let text = tr!("queue.dropping", files = text);
+
+        let painter =
+            ctx.layer_painter(LayerId::new(Order::Foreground, Id::new("file_drop_target")));
+
+        let screen_rect = ctx.screen_rect();
+        painter.rect_filled(screen_rect, 0.0, Color32::from_black_alpha(192));
+        painter.text(
+            screen_rect.center(),
+            Align2::CENTER_CENTER,
+            text,
+            TextStyle::Heading.resolve(&ctx.style()),
+            Color32::WHITE,
+        );
+        return Some(paths);
+    }
+    None
+}
+
+/// 输出目录、文件名模板和文件已存在时的处理
+pub fn output_policy_ui(ui: &mut egui::Ui, policy: &mut OutputPolicy) {
+    ui.label(tr!("output.dir"));
+    let mut dir = policy
+        .dir
+        .as_ref()
+        .map(|dir| dir.display().to_string())
+        .unwrap_or_default();
+    ui.horizontal(|ui| {
+        if path_edit(ui, &mut dir, tr!("output.dir_hint"), Browse::Folder) {
+            policy.dir = (!dir.is_empty()).then(|| PathBuf::from(dir));
+        }
+    });
+    ui.end_row();
+
+    ui.label(tr!("output.name"));
+    ui.add(egui::TextEdit::singleline(&mut policy.template).hint_text(DEFAULT_TEMPLATE))
+        .on_hover_text(tr!("output.name_help"));
+    ui.end_row();
+
+    ui.label(tr!("output.conflict"));
+    ui.horizontal(|ui| {
+        ui.radio_value(
+            &mut policy.conflict,
+            Conflict::Overwrite,
+            tr!("output.overwrite"),
+        );
+        ui.radio_value(&mut policy.conflict, Conflict::Skip, tr!("output.skip"));
+        ui.radio_value(&mut policy.conflict, Conflict::Suffix, tr!("output.suffix"));
+    });
+    ui.end_row();
+}
+
+/// 输入编码的选择，`save_normalized` 为真时处理时另存解码后的副本，`None` 时不显示这个选项
+///
+/// 旁边的按钮打开编码检查，可以看看 `files` 里的文件按各个编码解码的样子再选。
+#[cfg(any(feature = "hardfault", feature = "hci", feature = "convert"))]
+pub fn file_encoding_select(
+    ui: &mut egui::Ui,
+    encode: &mut FileEncoding,
+    save_normalized: Option<&mut bool>,
+    inspector: &mut EncodingInspector,
+    files: &[PathBuf],
+) {
+    ui.label(tr!("encoding.select"));
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_label(tr!("encoding.label"))
+            .selected_text(encode.name())
+            .show_ui(ui, |ui| {
+                for choice in FileEncoding::CHOICES {
+                    let name = choice.name().to_string();
+                    ui.selectable_value(encode, choice, name);
+                }
+                // 编码检查里选中的编码
+                if let FileEncoding::Named(name) = encode.clone() {
+                    ui.selectable_value(encode, FileEncoding::Named(name.clone()), name);
+                }
+            });
+        inspector.button(ui, files);
+    });
+    inspector.show(ui, files, encode);
+    ui.end_row();
+
+    if let Some(save_normalized) = save_normalized {
+        ui.label("");
+        ui.checkbox(save_normalized, tr!("encoding.save_normalized"))
+            .on_hover_text(tr!("encoding.save_normalized_help"));
+        ui.end_row();
+    }
+}
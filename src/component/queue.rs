@@ -0,0 +1,264 @@
+//! 页面上待处理的文件列表
+
+use std::path::{Path, PathBuf};
+
+use super::dialog::{folder_button, OpenFiles};
+use super::job::{JobError, JobOutputs};
+#[cfg(target_arch = "wasm32")]
+use super::output_label;
+use super::{has_extension, BatchResult};
+use crate::core;
+use crate::tr;
+
+enum FileStatus {
+    Pending,
+    Done(Vec<PathBuf>),
+    /// 输出已经存在，按输出策略跳过
+    Skipped(PathBuf),
+    Failed(String),
+}
+
+struct QueuedFile {
+    path: PathBuf,
+    status: FileStatus,
+}
+
+/// 每个页面记住的最近文件数
+const MAX_RECENT: usize = 10;
+
+/// 页面上待处理的文件列表，拖入文件夹时按扩展名递归加入其中的文件
+pub struct FileQueue {
+    files: Vec<QueuedFile>,
+    /// 网页版 [`crate::add_drop_file`] 按它过滤拖进来的文件
+    pub(super) extensions: &'static [&'static str],
+    input: String,
+    /// 最近处理过的文件，最新的在前面
+    recent: Vec<PathBuf>,
+    picker: OpenFiles,
+    /// 文件夹原样留在列表里，由页面处理时再展开
+    keep_folders: bool,
+}
+
+impl FileQueue {
+    pub fn new(extensions: &'static [&'static str]) -> Self {
+        FileQueue {
+            files: Vec::new(),
+            extensions,
+            input: String::new(),
+            recent: Vec::new(),
+            picker: OpenFiles::default(),
+            keep_folders: false,
+        }
+    }
+
+    /// 文件夹不展开，列表里可以直接添加文件夹
+    #[cfg(feature = "convert")]
+    pub fn with_folders(extensions: &'static [&'static str]) -> Self {
+        FileQueue {
+            keep_folders: true,
+            ..FileQueue::new(extensions)
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.files.iter().map(|f| f.path.clone()).collect()
+    }
+
+    pub fn add(&mut self, paths: impl IntoIterator<Item = PathBuf>) {
+        for path in paths {
+            if path.is_dir() && !self.keep_folders {
+                let mut found = Vec::new();
+                collect_dir(&path, self.extensions, &mut found);
+                found.sort();
+                for file in found {
+                    self.add_file(file);
+                }
+            } else {
+                self.add_file(path);
+            }
+        }
+    }
+
+    /// 换成 `paths`，之前的状态都清掉
+    pub fn replace(&mut self, paths: impl IntoIterator<Item = PathBuf>) {
+        self.files.clear();
+        self.add(paths);
+    }
+
+    pub fn recent(&self) -> &[PathBuf] {
+        &self.recent
+    }
+
+    /// 恢复上次保存的列表和最近文件，已经不存在的文件忽略
+    pub fn restore(&mut self, paths: &[PathBuf], recent: &[PathBuf]) {
+        let keep_folders = self.keep_folders;
+        let exists = |path: &&PathBuf| core::fs::is_file(path) || keep_folders && path.is_dir();
+        for path in paths.iter().filter(exists) {
+            self.add_file(path.clone());
+        }
+        self.recent = recent.iter().take(MAX_RECENT).cloned().collect();
+    }
+
+    fn remember(&mut self, path: &Path) {
+        self.recent.retain(|recent| recent != path);
+        self.recent.insert(0, path.to_path_buf());
+        self.recent.truncate(MAX_RECENT);
+    }
+
+    fn add_file(&mut self, path: PathBuf) {
+        if !self.files.iter().any(|f| f.path == path) {
+            self.files.push(QueuedFile {
+                path,
+                status: FileStatus::Pending,
+            });
+        }
+    }
+
+    /// `path` 是不是列表里某个文件处理后的输出
+    #[cfg(any(
+        feature = "logic",
+        feature = "hardfault",
+        feature = "hci",
+        feature = "analyze"
+    ))]
+    pub(super) fn is_output(&self, path: &Path) -> bool {
+        self.files.iter().any(|file| match &file.status {
+            FileStatus::Done(outputs) => outputs.iter().any(|output| output == path),
+            _ => false,
+        })
+    }
+
+    /// 把批量处理的结果更新到列表上
+    pub fn set_results<T: JobOutputs>(&mut self, batch: &BatchResult<T>) {
+        for (path, ret) in &batch.files {
+            // 取消时没有处理的文件保持原样
+            if matches!(ret, Err(JobError::Error(core::Error::Cancelled))) {
+                continue;
+            }
+            self.remember(path);
+            if let Some(file) = self.files.iter_mut().find(|f| &f.path == path) {
+                file.status = match ret {
+                    Ok(ret) => FileStatus::Done(ret.outputs()),
+                    Err(JobError::Error(core::Error::Skipped(path))) => {
+                        FileStatus::Skipped(path.clone())
+                    }
+                    Err(e) => FileStatus::Failed(e.message()),
+                };
+            }
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        if self.keep_folders {
+            ui.label(tr!("queue.files_and_folders"));
+        } else {
+            ui.label(tr!("queue.files"));
+        }
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.input);
+                if ui
+                    .add_enabled(!self.input.is_empty(), egui::Button::new(tr!("queue.add")))
+                    .clicked()
+                {
+                    let input = std::mem::take(&mut self.input);
+                    self.add([PathBuf::from(input)]);
+                }
+                if ui
+                    .add_enabled(
+                        !self.files.is_empty(),
+                        egui::Button::new(tr!("common.clear")),
+                    )
+                    .clicked()
+                {
+                    self.files.clear();
+                }
+                if let Some(paths) = self.picker.button(ui, self.extensions) {
+                    self.add(paths);
+                }
+                if self.keep_folders {
+                    if let Some(dir) = folder_button(ui) {
+                        self.add([dir]);
+                    }
+                }
+                let mut picked = None;
+                ui.add_enabled_ui(!self.recent.is_empty(), |ui| {
+                    ui.menu_button(tr!("queue.recent"), |ui| {
+                        for path in &self.recent {
+                            if ui.button(path.display().to_string()).clicked() {
+                                picked = Some(path.clone());
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                });
+                if let Some(path) = picked {
+                    self.add([path]);
+                }
+            });
+
+            let mut remove = None;
+            for (i, file) in self.files.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui.small_button("✖").clicked() {
+                        remove = Some(i);
+                    }
+                    if self.keep_folders && file.path.is_dir() {
+                        ui.label(format!("📁 {}", file.path.display()));
+                    } else {
+                        ui.label(file.path.display().to_string());
+                    }
+                    match &file.status {
+                        FileStatus::Pending => {}
+                        FileStatus::Done(outputs) => {
+                            ui.colored_label(egui::Color32::GREEN, "✔").on_hover_text(
+                                outputs
+                                    .iter()
+                                    .map(|p| p.display().to_string())
+                                    .collect::<Vec<_>>()
+                                    .join("\n"),
+                            );
+                            #[cfg(target_arch = "wasm32")]
+                            for output in outputs {
+                                output_label(ui, output);
+                            }
+                        }
+                        FileStatus::Skipped(path) => {
+                            ui.weak(tr!("queue.skipped"))
+                                .on_hover_text(tr!("queue.exists", path = path.display()));
+                        }
+                        FileStatus::Failed(e) => {
+                            ui.colored_label(ui.visuals().error_fg_color, format!("✖ {}", e));
+                        }
+                    }
+                });
+            }
+            if let Some(i) = remove {
+                self.files.remove(i);
+            }
+        });
+        ui.end_row();
+    }
+}
+
+/// 不进入指向文件夹的符号链接，链接成环时不会无限递归
+fn collect_dir(dir: &Path, extensions: &[&str], found: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            collect_dir(&path, extensions, found);
+        } else if (file_type.is_file() || path.is_file()) && has_extension(&path, extensions) {
+            found.push(path);
+        }
+    }
+}
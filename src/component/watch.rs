@@ -0,0 +1,188 @@
+//! 页面的监控文件夹
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use super::{has_extension, path_edit, Browse, FileQueue};
+use crate::tr;
+
+/// 监控文件夹的轮询间隔
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 后台轮询的文件夹监控，停止或者 drop 之后线程在下一次轮询时退出
+struct FolderWatcher {
+    stop: Arc<AtomicBool>,
+    rx: Receiver<PathBuf>,
+}
+
+impl FolderWatcher {
+    fn start(
+        ctx: &egui::Context,
+        dir: PathBuf,
+        extensions: &'static [&'static str],
+        outputs: &'static [&'static str],
+    ) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        let thread_stop = stop.clone();
+        thread::spawn(move || {
+            // 开始监控前已经存在的文件不处理
+            let mut seen: HashSet<PathBuf> = scan_dir(&dir, extensions, outputs)
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect();
+            // 新文件要两次轮询大小不变才认为写完了
+            let mut growing: HashMap<PathBuf, u64> = HashMap::new();
+
+            while !thread_stop.load(Ordering::Relaxed) {
+                thread::sleep(WATCH_INTERVAL);
+                let files = scan_dir(&dir, extensions, outputs);
+                // 删掉或者移走的文件不再记着，同名文件再出现时当作新文件
+                let present: HashSet<&PathBuf> = files.iter().map(|(path, _)| path).collect();
+                seen.retain(|path| present.contains(path));
+                growing.retain(|path, _| present.contains(path));
+                for (path, len) in files {
+                    if seen.contains(&path) {
+                        continue;
+                    }
+                    if growing.insert(path.clone(), len) == Some(len) {
+                        growing.remove(&path);
+                        seen.insert(path.clone());
+                        log::info!("watch: new file {}", path.display());
+                        if tx.send(path).is_err() {
+                            return;
+                        }
+                        ctx.request_repaint();
+                    }
+                }
+            }
+        });
+        FolderWatcher { stop, rx }
+    }
+}
+
+impl Drop for FolderWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn scan_dir(dir: &Path, extensions: &[&str], outputs: &[&str]) -> Vec<(PathBuf, u64)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            let path = entry.path();
+            (meta.is_file() && has_extension(&path, extensions) && !is_output(&path, outputs))
+                .then_some((path, meta.len()))
+        })
+        .collect()
+}
+
+/// 文件名以 `.{format}` 结尾时认为是工具自己写的输出，比如 `crash.log.regs.txt`
+fn is_output(path: &Path, formats: &[&str]) -> bool {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    formats
+        .iter()
+        .any(|format| name.ends_with(&format!(".{}", format)))
+}
+
+/// 页面的监控文件夹，发现新文件后加入文件列表，等页面空闲时用当前的选项处理
+pub struct FolderWatch {
+    watcher: Option<FolderWatcher>,
+    pending: Vec<PathBuf>,
+    extensions: &'static [&'static str],
+    /// 工具输出的格式，和扩展名重合时不能当成新的输入
+    outputs: &'static [&'static str],
+}
+
+impl FolderWatch {
+    pub fn new(extensions: &'static [&'static str], outputs: &'static [&'static str]) -> Self {
+        FolderWatch {
+            watcher: None,
+            pending: Vec::new(),
+            extensions,
+            outputs,
+        }
+    }
+
+    pub fn is_watching(&self) -> bool {
+        self.watcher.is_some()
+    }
+
+    pub fn start(&mut self, ctx: &egui::Context, dir: &str) {
+        // 浏览器里不能访问文件夹
+        if dir.is_empty() || cfg!(target_arch = "wasm32") {
+            return;
+        }
+        log::info!("watch: start {}", dir);
+        self.watcher = Some(FolderWatcher::start(
+            ctx,
+            PathBuf::from(dir),
+            self.extensions,
+            self.outputs,
+        ));
+    }
+
+    pub fn stop(&mut self) {
+        self.watcher = None;
+        self.pending.clear();
+    }
+
+    /// 把新发现的文件加入列表，返回需要处理的文件；页面正忙时先攒着
+    pub fn poll(&mut self, files: &mut FileQueue, busy: bool) -> Option<Vec<PathBuf>> {
+        if let Some(watcher) = &self.watcher {
+            for path in watcher.rx.try_iter() {
+                // 输出模板不以格式结尾时靠这里排除
+                if files.is_output(&path) {
+                    continue;
+                }
+                files.add([path.clone()]);
+                self.pending.push(path);
+            }
+        }
+        if busy || self.pending.is_empty() {
+            return None;
+        }
+        Some(std::mem::take(&mut self.pending))
+    }
+
+    /// `dir` 和 `enabled` 是页面保存的设置
+    pub fn ui(&mut self, ui: &mut egui::Ui, dir: &mut String, enabled: &mut bool) {
+        if cfg!(target_arch = "wasm32") {
+            return;
+        }
+        ui.label(tr!("watch.folder"));
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(!self.is_watching(), |ui| {
+                path_edit(ui, dir, tr!("watch.hint"), Browse::Folder);
+            });
+            if self.is_watching() {
+                ui.spinner();
+                if ui.button(tr!("watch.stop")).clicked() {
+                    self.stop();
+                    *enabled = false;
+                }
+            } else if ui
+                .add_enabled(!dir.is_empty(), egui::Button::new(tr!("watch.start")))
+                .clicked()
+            {
+                self.start(ui.ctx(), dir);
+                *enabled = true;
+            }
+        });
+        ui.end_row();
+    }
+}
@@ -0,0 +1,54 @@
+//! 网页版的文件输入和下载，浏览器里拿不到文件路径，文件内容放在 [`core::fs`] 的内存里
+
+use std::path::{Path, PathBuf};
+
+use eframe::wasm_bindgen::JsCast as _;
+
+use crate::core;
+
+/// 这一帧拖进来的文件，内容放进内存，返回用文件名做的路径
+pub fn dropped_files(ctx: &egui::Context) -> Vec<PathBuf> {
+    ctx.input(|i| {
+        i.raw
+            .dropped_files
+            .iter()
+            .filter_map(|file| {
+                let bytes = file.bytes.clone()?;
+                let path = PathBuf::from(&file.name);
+                core::fs::insert(path.clone(), bytes);
+                Some(path)
+            })
+            .collect()
+    })
+}
+
+/// 把内存里的输出文件交给浏览器下载
+pub fn download(path: &Path) {
+    let Ok(bytes) = core::fs::read(path) else {
+        log::error!("download: {} not found", path.display());
+        return;
+    };
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if save_blob(&name, &bytes).is_none() {
+        log::error!("download: failed to save {}", name);
+    }
+}
+
+fn save_blob(name: &str, bytes: &[u8]) -> Option<()> {
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+    let blob = web_sys::Blob::new_with_u8_array_sequence(&parts).ok()?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).ok()?;
+    let anchor = web_sys::window()?
+        .document()?
+        .create_element("a")
+        .ok()?
+        .dyn_into::<web_sys::HtmlAnchorElement>()
+        .ok()?;
+    anchor.set_href(&url);
+    anchor.set_download(name);
+    anchor.click();
+    web_sys::Url::revoke_object_url(&url).ok()
+}
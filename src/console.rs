@@ -0,0 +1,234 @@
+//! 应用内的日志窗口，收集本程序通过 `log` 打印的记录，可以按任务和级别筛选
+//!
+//! 后台任务运行期间打印的记录会带上任务名，见 [`JobScope`]。
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use once_cell::sync::Lazy;
+
+use crate::tr;
+
+/// 最多保留的记录数，超出时丢掉最旧的
+const MAX_ENTRIES: usize = 5000;
+
+/// 窗口里收集的最低级别
+const CAPTURE_LEVEL: LevelFilter = LevelFilter::Debug;
+
+#[derive(Clone)]
+struct LogEntry {
+    time: String,
+    level: Level,
+    job: Option<Arc<str>>,
+    message: String,
+}
+
+impl LogEntry {
+    fn line(&self) -> String {
+        match &self.job {
+            Some(job) => format!("{} {:5} [{}] {}", self.time, self.level, job, self.message),
+            None => format!("{} {:5} {}", self.time, self.level, self.message),
+        }
+    }
+}
+
+static ENTRIES: Lazy<Mutex<VecDeque<LogEntry>>> = Lazy::new(Default::default);
+
+fn entries() -> MutexGuard<'static, VecDeque<LogEntry>> {
+    ENTRIES.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+thread_local! {
+    static CURRENT_JOB: RefCell<Option<Arc<str>>> = const { RefCell::new(None) };
+}
+
+/// 作用域内当前线程打印的记录都属于任务 `job`
+pub struct JobScope {
+    previous: Option<Arc<str>>,
+}
+
+impl JobScope {
+    pub fn enter(job: &str) -> Self {
+        let previous = CURRENT_JOB.with(|current| current.replace(Some(job.into())));
+        JobScope { previous }
+    }
+}
+
+impl Drop for JobScope {
+    fn drop(&mut self) {
+        CURRENT_JOB.with(|current| *current.borrow_mut() = self.previous.take());
+    }
+}
+
+/// 每个任务的编号，任务名里带上编号，重复运行时也能分开筛选
+pub fn next_job_id() -> usize {
+    static NEXT: AtomicUsize = AtomicUsize::new(1);
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+/// 收集记录，同时交给 `inner`（终端或者浏览器控制台）
+struct ConsoleLogger {
+    inner: Option<Box<dyn Log>>,
+}
+
+impl ConsoleLogger {
+    fn captures(metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= CAPTURE_LEVEL && metadata.target().starts_with(env!("CARGO_CRATE_NAME"))
+    }
+}
+
+impl Log for ConsoleLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        Self::captures(metadata)
+            || self
+                .inner
+                .as_ref()
+                .is_some_and(|inner| inner.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if Self::captures(record.metadata()) {
+            let entry = LogEntry {
+                time: chrono::Local::now().format("%H:%M:%S%.3f").to_string(),
+                level: record.level(),
+                job: CURRENT_JOB.with(|current| current.borrow().clone()),
+                message: record.args().to_string(),
+            };
+            let mut entries = entries();
+            if entries.len() == MAX_ENTRIES {
+                entries.pop_front();
+            }
+            entries.push_back(entry);
+        }
+        if let Some(inner) = &self.inner {
+            if inner.enabled(record.metadata()) {
+                inner.log(record);
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(inner) = &self.inner {
+            inner.flush();
+        }
+    }
+}
+
+/// 安装全局 logger，`inner` 的过滤条件不影响日志窗口
+pub fn init(
+    inner: Option<Box<dyn Log>>,
+    inner_level: LevelFilter,
+) -> Result<(), log::SetLoggerError> {
+    log::set_boxed_logger(Box::new(ConsoleLogger { inner }))?;
+    log::set_max_level(inner_level.max(CAPTURE_LEVEL));
+    Ok(())
+}
+
+/// 日志窗口的筛选条件
+pub struct Console {
+    level: LevelFilter,
+    /// 为 `None` 时显示所有任务
+    job: Option<Arc<str>>,
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Console {
+            level: LevelFilter::Info,
+            job: None,
+        }
+    }
+}
+
+impl Console {
+    fn matches(&self, entry: &LogEntry) -> bool {
+        entry.level <= self.level
+            && match &self.job {
+                Some(job) => entry.job.as_ref() == Some(job),
+                None => true,
+            }
+    }
+
+    /// 只在读写记录时短暂拿锁，画界面期间后台线程照常打印
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.heading(tr!("console.heading"));
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label(tr!("console.level"))
+                .selected_text(self.level.as_str())
+                .show_ui(ui, |ui| {
+                    for level in [
+                        LevelFilter::Error,
+                        LevelFilter::Warn,
+                        LevelFilter::Info,
+                        LevelFilter::Debug,
+                    ] {
+                        ui.selectable_value(&mut self.level, level, level.as_str());
+                    }
+                });
+
+            // 最新的任务在前面
+            let mut jobs: Vec<Arc<str>> = Vec::new();
+            for job in entries()
+                .iter()
+                .rev()
+                .filter_map(|entry| entry.job.as_ref())
+            {
+                if !jobs.contains(job) {
+                    jobs.push(job.clone());
+                }
+            }
+            egui::ComboBox::from_label(tr!("console.job"))
+                .selected_text(self.job.as_deref().unwrap_or(tr!("console.all_jobs")))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.job, None, tr!("console.all_jobs"));
+                    for job in jobs {
+                        let text = job.to_string();
+                        ui.selectable_value(&mut self.job, Some(job), text);
+                    }
+                });
+
+            if ui.button(tr!("console.copy")).clicked() {
+                let text = entries()
+                    .iter()
+                    .filter(|entry| self.matches(entry))
+                    .map(LogEntry::line)
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                ui.ctx().copy_text(text);
+            }
+            if ui.button(tr!("common.clear")).clicked() {
+                entries().clear();
+                self.job = None;
+            }
+        });
+        ui.separator();
+
+        // 只复制看得见的行再画
+        let count = entries().iter().filter(|entry| self.matches(entry)).count();
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        egui::ScrollArea::both()
+            .auto_shrink(false)
+            .stick_to_bottom(true)
+            .show_rows(ui, row_height, count, |ui, rows| {
+                let shown: Vec<LogEntry> = entries()
+                    .iter()
+                    .filter(|entry| self.matches(entry))
+                    .skip(rows.start)
+                    .take(rows.len())
+                    .cloned()
+                    .collect();
+                for entry in &shown {
+                    let color = match entry.level {
+                        Level::Error => ui.visuals().error_fg_color,
+                        Level::Warn => ui.visuals().warn_fg_color,
+                        Level::Info => ui.visuals().text_color(),
+                        Level::Debug | Level::Trace => ui.visuals().weak_text_color(),
+                    };
+                    ui.label(egui::RichText::new(entry.line()).monospace().color(color));
+                }
+            });
+    }
+}
@@ -0,0 +1,160 @@
+//! 从 SPI 抓到的打印里筛选指定类型的消息
+
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+use super::{fs, Error, Progress, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogMessage {
+    pub timestamp: f64,
+    pub content: String,
+}
+
+/// 把逐字节的 MOSI 数据拼成以 `LF` 结尾的消息，只保留带 `:` 的消息
+pub fn process_logic_data(reader: impl BufRead) -> Result<Vec<LogMessage>> {
+    let mut messages = Vec::new();
+    for_each_message(reader, |message| {
+        messages.push(message);
+        Ok(())
+    })?;
+    Ok(messages)
+}
+
+/// 和 [`process_logic_data`] 一样，每拼出一条消息就回调，不保存所有消息
+pub fn for_each_message(
+    reader: impl BufRead,
+    mut f: impl FnMut(LogMessage) -> Result<()>,
+) -> Result<()> {
+    let mut current_message = String::with_capacity(100); // 预分配内存
+    let mut start_time: Option<f64> = None;
+
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .flexible(true)
+        .from_reader(reader);
+
+    // 获取并缓存列索引
+    let headers = csv_reader.headers()?;
+    let time_idx = headers
+        .iter()
+        .position(|h| h == "Time [s]")
+        .ok_or(Error::MissingColumn("Time [s]"))?;
+    let mosi_idx = headers
+        .iter()
+        .position(|h| h == "MOSI")
+        .ok_or(Error::MissingColumn("MOSI"))?;
+
+    // 使用 into_records 避免克隆
+    for result in csv_reader.into_records() {
+        let record = result?;
+        let invalid = || Error::InvalidValue {
+            line: record.position().map_or(0, |p| p.line() as usize),
+            content: record.iter().collect::<Vec<_>>().join(","),
+        };
+
+        let time: f64 = record
+            .get(time_idx)
+            .ok_or_else(invalid)?
+            .parse()
+            .map_err(|_| invalid())?;
+
+        let char = record.get(mosi_idx).ok_or_else(invalid)?;
+
+        if start_time.is_none() {
+            start_time = Some(time);
+        }
+
+        match char {
+            "NUL" => current_message.push(' '),
+            "LF " => {
+                if !current_message.is_empty() {
+                    let message = current_message.trim().to_string();
+                    if message.contains(':') {
+                        if let Some(timestamp) = start_time {
+                            f(LogMessage {
+                                timestamp,
+                                content: message,
+                            })?;
+                        }
+                        start_time = None;
+                    }
+                }
+                current_message.clear();
+            }
+            c => current_message.push_str(c),
+        }
+    }
+
+    Ok(())
+}
+
+#[inline]
+fn validate_message_type(message: &str, map: &HashSet<String>) -> bool {
+    if let Some(msg_type) = message.split(':').next() {
+        map.contains(msg_type)
+    } else {
+        false
+    }
+}
+
+fn valid_types(types: &str) -> HashSet<String> {
+    types.split(',').map(|s| s.trim().to_string()).collect()
+}
+
+/// 筛选 `types`（逗号分隔）中的消息类型
+pub fn filter_messages(types: &str, reader: impl BufRead) -> Result<Vec<LogMessage>> {
+    let valid_types = valid_types(types);
+
+    let messages = process_logic_data(reader)?;
+
+    Ok(messages
+        .into_iter()
+        .filter(|msg| validate_message_type(&msg.content, &valid_types))
+        .collect())
+}
+
+fn write_message<W: Write>(message: &LogMessage, writer: &mut W) -> Result<()> {
+    writeln!(writer, "[{:.6}]{}", message.timestamp, message.content)?;
+    Ok(())
+}
+
+pub fn write_output<W: Write>(messages: &[LogMessage], writer: &mut W) -> Result<()> {
+    for message in messages {
+        write_message(message, writer)?;
+    }
+    Ok(())
+}
+
+/// 边读边筛选消息并写出，返回写出的消息数
+pub fn analyze<R: BufRead, W: Write>(types: &str, reader: R, writer: &mut W) -> Result<usize> {
+    let valid_types = valid_types(types);
+    let mut count = 0;
+    for_each_message(reader, |message| {
+        if validate_message_type(&message.content, &valid_types) {
+            write_message(&message, writer)?;
+            count += 1;
+        }
+        Ok(())
+    })?;
+    Ok(count)
+}
+
+/// 筛选结果的输出格式
+pub const OUTPUT_FORMAT: &str = "out.txt";
+
+/// 筛选 `input_file` 写到 `output_file`，返回写出的消息数
+pub fn analyze_file(
+    types: &str,
+    input_file: &Path,
+    output_file: &Path,
+    progress: &Progress,
+) -> Result<usize> {
+    if fs::same_file(input_file, output_file) {
+        return Err(Error::OutputIsInput(output_file.to_path_buf()));
+    }
+    let file = progress.open(input_file)?;
+    let reader = BufReader::with_capacity(128 * 1024, file);
+    fs::write_atomic(output_file, |writer| analyze(types, reader, writer))
+}
@@ -0,0 +1,483 @@
+//! 批量转换文本文件的编码和换行符
+//!
+//! 输入按 [`FileEncoding`] 解码（可以每个文件自己猜测），换行符按需要统一后编码成
+//! [`TargetEncoding`]。输入文件不会被修改，输出位置由 [`OutputPolicy`] 决定；
+//! 预览时走同样的流程但不写文件，用来在转换前检查每个文件的情况。
+
+use std::collections::HashSet;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use encoding_rs::{Encoder, EncoderResult, Encoding};
+
+use super::encoding::{self, Charset, DecodeError, DecodeReader, FileEncoding};
+use super::{fs, Error, OutputPolicy, Progress, Result};
+
+/// 输出编码，UTF-16 和 UTF-32 总是带 BOM
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub enum TargetEncoding {
+    #[default]
+    UTF8,
+    /// 带 BOM 的 UTF-8，部分 Windows 工具需要
+    UTF8Bom,
+    UTF16LE,
+    UTF16BE,
+    UTF32LE,
+    UTF32BE,
+    GBK,
+    GB18030,
+    Big5,
+    ShiftJIS,
+}
+
+impl TargetEncoding {
+    pub const ALL: [TargetEncoding; 10] = [
+        TargetEncoding::UTF8,
+        TargetEncoding::UTF8Bom,
+        TargetEncoding::UTF16LE,
+        TargetEncoding::UTF16BE,
+        TargetEncoding::UTF32LE,
+        TargetEncoding::UTF32BE,
+        TargetEncoding::GBK,
+        TargetEncoding::GB18030,
+        TargetEncoding::Big5,
+        TargetEncoding::ShiftJIS,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TargetEncoding::UTF8 => "UTF-8",
+            TargetEncoding::UTF8Bom => "UTF-8 BOM",
+            TargetEncoding::UTF16LE => "UTF-16LE",
+            TargetEncoding::UTF16BE => "UTF-16BE",
+            TargetEncoding::UTF32LE => "UTF-32LE",
+            TargetEncoding::UTF32BE => "UTF-32BE",
+            TargetEncoding::GBK => "GBK",
+            TargetEncoding::GB18030 => "GB18030",
+            TargetEncoding::Big5 => "Big5",
+            TargetEncoding::ShiftJIS => "Shift_JIS",
+        }
+    }
+
+    /// 输出文件名模板里的 `{format}`
+    pub fn format(self) -> &'static str {
+        match self {
+            TargetEncoding::UTF8 | TargetEncoding::UTF8Bom => "utf8",
+            TargetEncoding::UTF16LE => "utf16le",
+            TargetEncoding::UTF16BE => "utf16be",
+            TargetEncoding::UTF32LE => "utf32le",
+            TargetEncoding::UTF32BE => "utf32be",
+            TargetEncoding::GBK => "gbk",
+            TargetEncoding::GB18030 => "gb18030",
+            TargetEncoding::Big5 => "big5",
+            TargetEncoding::ShiftJIS => "sjis",
+        }
+    }
+}
+
+/// 输出的换行符
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub enum LineEnding {
+    /// 保持原样
+    #[default]
+    Keep,
+    Lf,
+    CrLf,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ConvertOptions {
+    /// 输入编码，`Other` 时每个文件分别猜测
+    pub source: FileEncoding,
+    pub target: TargetEncoding,
+    pub line_ending: LineEnding,
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        ConvertOptions {
+            source: FileEncoding::Other,
+            target: TargetEncoding::default(),
+            line_ending: LineEnding::default(),
+        }
+    }
+}
+
+/// [`convert`] 统计的内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ConvertStats {
+    /// 输入里各种换行符的个数，单独的 `\r` 算作 `cr`
+    pub crlf: u64,
+    pub lf: u64,
+    pub cr: u64,
+    /// 目标编码里没有、写成 `?` 的字符数
+    pub unmappable: u64,
+}
+
+/// 一个文件的转换结果，预览时也会生成，只是不写文件
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvertReport {
+    /// 实际使用的输入编码
+    pub source: Charset,
+    pub output: PathBuf,
+    /// 预览时为 false
+    pub written: bool,
+    pub stats: ConvertStats,
+    /// 前几处无法解码的位置，总数是 `decode_error_count`
+    pub decode_errors: Vec<DecodeError>,
+    pub decode_error_count: u64,
+}
+
+/// 每次处理的解码后文本大小
+const CHUNK: usize = 64 * 1024;
+
+/// 按目标编码写出文本
+enum TextEncoder {
+    Utf8,
+    Utf16 { big_endian: bool },
+    Utf32 { big_endian: bool },
+    Standard(Encoder),
+}
+
+impl TextEncoder {
+    fn new(target: TargetEncoding) -> Self {
+        let standard = |encoding: &'static Encoding| TextEncoder::Standard(encoding.new_encoder());
+        match target {
+            TargetEncoding::UTF8 | TargetEncoding::UTF8Bom => TextEncoder::Utf8,
+            TargetEncoding::UTF16LE => TextEncoder::Utf16 { big_endian: false },
+            TargetEncoding::UTF16BE => TextEncoder::Utf16 { big_endian: true },
+            TargetEncoding::UTF32LE => TextEncoder::Utf32 { big_endian: false },
+            TargetEncoding::UTF32BE => TextEncoder::Utf32 { big_endian: true },
+            TargetEncoding::GBK => standard(encoding_rs::GBK),
+            TargetEncoding::GB18030 => standard(encoding_rs::GB18030),
+            TargetEncoding::Big5 => standard(encoding_rs::BIG5),
+            TargetEncoding::ShiftJIS => standard(encoding_rs::SHIFT_JIS),
+        }
+    }
+
+    fn bom(target: TargetEncoding) -> &'static [u8] {
+        match target {
+            TargetEncoding::UTF8Bom => &[0xEF, 0xBB, 0xBF],
+            TargetEncoding::UTF16LE => &[0xFF, 0xFE],
+            TargetEncoding::UTF16BE => &[0xFE, 0xFF],
+            TargetEncoding::UTF32LE => &[0xFF, 0xFE, 0x00, 0x00],
+            TargetEncoding::UTF32BE => &[0x00, 0x00, 0xFE, 0xFF],
+            _ => &[],
+        }
+    }
+
+    /// 把 `text` 编码后追加到 `out`，返回写成 `?` 的字符数
+    fn encode(&mut self, text: &str, out: &mut Vec<u8>, last: bool) -> u64 {
+        match self {
+            TextEncoder::Utf8 => out.extend_from_slice(text.as_bytes()),
+            TextEncoder::Utf16 { big_endian } => {
+                for unit in text.encode_utf16() {
+                    out.extend_from_slice(&if *big_endian {
+                        unit.to_be_bytes()
+                    } else {
+                        unit.to_le_bytes()
+                    });
+                }
+            }
+            TextEncoder::Utf32 { big_endian } => {
+                for c in text.chars() {
+                    out.extend_from_slice(&if *big_endian {
+                        (c as u32).to_be_bytes()
+                    } else {
+                        (c as u32).to_le_bytes()
+                    });
+                }
+            }
+            TextEncoder::Standard(encoder) => {
+                let mut unmappable = 0;
+                let mut src = text;
+                loop {
+                    let start = out.len();
+                    let max = encoder
+                        .max_buffer_length_from_utf8_without_replacement(src.len())
+                        .unwrap_or(src.len() * 4 + 16);
+                    out.resize(start + max, 0);
+                    let (result, read, written) =
+                        encoder.encode_from_utf8_without_replacement(src, &mut out[start..], last);
+                    out.truncate(start + written);
+                    src = &src[read..];
+                    match result {
+                        EncoderResult::InputEmpty => return unmappable,
+                        EncoderResult::OutputFull => {}
+                        EncoderResult::Unmappable(_) => {
+                            out.push(b'?');
+                            unmappable += 1;
+                        }
+                    }
+                }
+            }
+        }
+        0
+    }
+}
+
+/// 统一换行符，`\r` 可能在上一块的结尾，用 `pending_cr` 记下
+struct LineEndings {
+    style: LineEnding,
+    pending_cr: bool,
+    stats: ConvertStats,
+}
+
+impl LineEndings {
+    fn push_break(&mut self, out: &mut String, original: &str) {
+        out.push_str(match self.style {
+            LineEnding::Keep => original,
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        });
+    }
+
+    fn convert(&mut self, text: &str, out: &mut String) {
+        let mut rest = text;
+        while let Some(i) = rest.find(['\r', '\n']) {
+            if std::mem::take(&mut self.pending_cr) {
+                if i == 0 && rest.starts_with('\n') {
+                    self.stats.crlf += 1;
+                    self.push_break(out, "\r\n");
+                    rest = &rest[1..];
+                    continue;
+                }
+                self.stats.cr += 1;
+                self.push_break(out, "\r");
+            }
+            out.push_str(&rest[..i]);
+            if rest[i..].starts_with('\r') {
+                self.pending_cr = true;
+            } else {
+                self.stats.lf += 1;
+                self.push_break(out, "\n");
+            }
+            rest = &rest[i + 1..];
+        }
+        if !rest.is_empty() && std::mem::take(&mut self.pending_cr) {
+            self.stats.cr += 1;
+            self.push_break(out, "\r");
+        }
+        out.push_str(rest);
+    }
+
+    fn finish(&mut self, out: &mut String) {
+        if std::mem::take(&mut self.pending_cr) {
+            self.stats.cr += 1;
+            self.push_break(out, "\r");
+        }
+    }
+}
+
+/// 把 utf8 的 `reader` 转成 `options` 的编码和换行符写到 `writer`
+///
+/// `reader` 一般是 [`DecodeReader`]，内容不是合法 utf8 时返回 [`Error::NotUtf8`]。
+pub fn convert<R: Read, W: Write>(
+    mut reader: R,
+    writer: &mut W,
+    options: &ConvertOptions,
+) -> Result<ConvertStats> {
+    let mut encoder = TextEncoder::new(options.target);
+    let mut lines = LineEndings {
+        style: options.line_ending,
+        pending_cr: false,
+        stats: ConvertStats::default(),
+    };
+    let mut unmappable = 0;
+    writer.write_all(TextEncoder::bom(options.target))?;
+
+    let mut buf = vec![0; CHUNK];
+    // 上一块结尾被切开的字符
+    let mut carry = 0;
+    let mut text = String::with_capacity(CHUNK * 2);
+    let mut out = Vec::with_capacity(CHUNK * 4);
+    loop {
+        let n = reader.read(&mut buf[carry..])?;
+        let len = carry + n;
+        let valid = match std::str::from_utf8(&buf[..len]) {
+            Ok(_) => len,
+            // 字符被切开，剩下的留到下一块
+            Err(e) if e.error_len().is_none() && n > 0 => e.valid_up_to(),
+            Err(_) => return Err(Error::NotUtf8),
+        };
+        let chunk = std::str::from_utf8(&buf[..valid]).map_err(|_| Error::NotUtf8)?;
+
+        text.clear();
+        out.clear();
+        lines.convert(chunk, &mut text);
+        if n == 0 {
+            lines.finish(&mut text);
+        }
+        unmappable += encoder.encode(&text, &mut out, n == 0);
+        writer.write_all(&out)?;
+
+        if n == 0 {
+            break;
+        }
+        buf.copy_within(valid..len, 0);
+        carry = len - valid;
+    }
+    Ok(ConvertStats {
+        unmappable,
+        ..lines.stats
+    })
+}
+
+/// 转换一个文件，`dry_run` 时只生成报告，不创建任何文件
+pub fn convert_file(
+    path: &Path,
+    options: &ConvertOptions,
+    output: &OutputPolicy,
+    dry_run: bool,
+    progress: &Progress,
+) -> Result<ConvertReport> {
+    let out = if dry_run {
+        output.plan(path, options.target.format())?
+    } else {
+        output.resolve(path, options.target.format())?
+    };
+    // utf8 也要解码一遍，才能发现不合法的字节
+    let source =
+        encoding::resolve(path, &options.source).unwrap_or(Charset::Standard(encoding_rs::UTF_8));
+    let mut reader = DecodeReader::new(progress.open(path)?, Some(source));
+    let stats = if dry_run {
+        convert(&mut reader, &mut io::sink(), options)?
+    } else {
+        fs::write_atomic(&out, |writer| convert(&mut reader, writer, options))?
+    };
+    encoding::warn_errors(path, &reader);
+    if stats.unmappable > 0 {
+        log::warn!(
+            "{}: {} character(s) not in {}, written as '?'",
+            path.display(),
+            stats.unmappable,
+            options.target.name()
+        );
+    }
+    Ok(ConvertReport {
+        source,
+        output: out,
+        written: !dry_run,
+        stats,
+        decode_errors: reader.errors().to_vec(),
+        decode_error_count: reader.error_count(),
+    })
+}
+
+/// 输出目录里保持输入文件夹的结构
+///
+/// 设置了输出目录时，`roots` 里某个文件夹下的文件写到输出目录里对应的子目录。
+pub fn mirror(output: &OutputPolicy, roots: &[PathBuf], path: &Path) -> OutputPolicy {
+    let mut output = output.clone();
+    if let Some(dir) = &mut output.dir {
+        let relative = path
+            .parent()
+            .and_then(|parent| roots.iter().find_map(|root| parent.strip_prefix(root).ok()));
+        if let Some(relative) = relative {
+            *dir = dir.join(relative);
+        }
+    }
+    output
+}
+
+/// `path` 在 `roots` 的第几项下面和相对的位置，不在任何一项下面时返回 `None`
+///
+/// 保存的是相对位置，列表换了目录之后用 [`from_root`] 还原。
+pub fn relative_to_root(roots: &[PathBuf], path: &Path) -> Option<(usize, PathBuf)> {
+    roots
+        .iter()
+        .enumerate()
+        .find_map(|(i, root)| Some((i, path.strip_prefix(root).ok()?.to_path_buf())))
+}
+
+/// [`relative_to_root`] 的反向，`relative` 为空时就是这一项本身
+pub fn from_root(roots: &[PathBuf], root: usize, relative: &Path) -> Option<PathBuf> {
+    let root = roots.get(root)?;
+    if relative.as_os_str().is_empty() {
+        Some(root.clone())
+    } else {
+        Some(root.join(relative))
+    }
+}
+
+/// 展开输入：文件直接加入，文件夹里按 `patterns` 匹配文件名
+///
+/// `patterns` 是用空格、逗号或分号分开的通配符，比如 `*.log *.txt`，支持 `*` 和 `?`，
+/// 不区分大小写，为空时匹配所有文件。`recursive` 为真时也查找子文件夹。
+pub fn collect(roots: &[PathBuf], recursive: bool, patterns: &str) -> Vec<PathBuf> {
+    let patterns: Vec<&str> = patterns
+        .split([' ', ',', ';'])
+        .filter(|pattern| !pattern.is_empty())
+        .collect();
+    let mut found = Vec::new();
+    for root in roots {
+        if root.is_dir() {
+            collect_dir(root, recursive, &patterns, &mut found);
+        } else if fs::is_file(root) {
+            found.push(root.clone());
+        }
+    }
+    let mut seen = HashSet::new();
+    found.retain(|path| seen.insert(path.clone()));
+    found
+}
+
+/// 不进入指向文件夹的符号链接，链接成环时不会无限递归
+fn collect_dir(dir: &Path, recursive: bool, patterns: &[&str], found: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries: Vec<_> = entries
+        .flatten()
+        .filter_map(|entry| Some((entry.path(), entry.file_type().ok()?)))
+        .collect();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (path, file_type) in entries {
+        if file_type.is_dir() {
+            if recursive {
+                collect_dir(&path, recursive, patterns, found);
+            }
+        } else if (file_type.is_file() || path.is_file()) && matches(&path, patterns) {
+            found.push(path);
+        }
+    }
+}
+
+fn matches(path: &Path, patterns: &[&str]) -> bool {
+    patterns.is_empty()
+        || path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .is_some_and(|name| patterns.iter().any(|p| glob_match(p, &name)))
+}
+
+/// 文件名通配符，`*` 匹配任意个字符，`?` 匹配一个字符
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // 上一个 `*` 的位置和它匹配到的名字位置，失配时回到这里多匹配一个字符
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
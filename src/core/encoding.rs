@@ -0,0 +1,573 @@
+//! 输入文件的编码检测和解码
+//!
+//! 输入文件不会被修改：转换工具通过 [`open`] 读取，读的时候解码成 utf8。
+//! 需要 utf8 的文件时用 [`save_normalized`] 另存一份。
+//!
+//! 文件开头有 BOM 时以 BOM 为准。`encoding_rs` 不支持 UTF-32，用 [`Charset`] 里自己的解码器。
+
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use encoding_rs::{Decoder, DecoderResult, Encoding};
+
+use super::{fs, OutputPolicy, Progress, ProgressReader, Result};
+
+#[derive(Debug, PartialEq, Clone, Default, serde::Deserialize, serde::Serialize)]
+pub enum FileEncoding {
+    #[default]
+    UTF8,
+    #[serde(alias = "UTF16")]
+    UTF16LE,
+    UTF16BE,
+    #[serde(alias = "UTF32")]
+    UTF32LE,
+    UTF32BE,
+    GBK,
+    GB18030,
+    Big5,
+    ShiftJIS,
+    Other,
+    /// 在编码检查里选中的编码，保存 [`Charset::name`]
+    Named(String),
+}
+
+impl FileEncoding {
+    /// 界面和命令行里可以直接选的编码
+    pub const CHOICES: [FileEncoding; 10] = [
+        FileEncoding::UTF8,
+        FileEncoding::UTF16LE,
+        FileEncoding::UTF16BE,
+        FileEncoding::UTF32LE,
+        FileEncoding::UTF32BE,
+        FileEncoding::GBK,
+        FileEncoding::GB18030,
+        FileEncoding::Big5,
+        FileEncoding::ShiftJIS,
+        FileEncoding::Other,
+    ];
+
+    pub fn name(&self) -> &str {
+        match self {
+            FileEncoding::UTF8 => "UTF8",
+            FileEncoding::UTF16LE => "UTF16LE",
+            FileEncoding::UTF16BE => "UTF16BE",
+            FileEncoding::UTF32LE => "UTF32LE",
+            FileEncoding::UTF32BE => "UTF32BE",
+            FileEncoding::GBK => "GBK",
+            FileEncoding::GB18030 => "GB18030",
+            FileEncoding::Big5 => "Big5",
+            FileEncoding::ShiftJIS => "Shift_JIS",
+            FileEncoding::Other => "Other",
+            FileEncoding::Named(name) => name,
+        }
+    }
+
+    /// 对应的字符集，`Other` 要先猜测所以返回 `None`
+    pub fn charset(&self) -> Option<Charset> {
+        let charset = match self {
+            FileEncoding::UTF8 => Charset::Standard(encoding_rs::UTF_8),
+            FileEncoding::UTF16LE => Charset::Standard(encoding_rs::UTF_16LE),
+            FileEncoding::UTF16BE => Charset::Standard(encoding_rs::UTF_16BE),
+            FileEncoding::UTF32LE => Charset::Utf32Le,
+            FileEncoding::UTF32BE => Charset::Utf32Be,
+            FileEncoding::GBK => Charset::Standard(encoding_rs::GBK),
+            FileEncoding::GB18030 => Charset::Standard(encoding_rs::GB18030),
+            FileEncoding::Big5 => Charset::Standard(encoding_rs::BIG5),
+            FileEncoding::ShiftJIS => Charset::Standard(encoding_rs::SHIFT_JIS),
+            FileEncoding::Other => return None,
+            FileEncoding::Named(name) => return Charset::for_label(name),
+        };
+        Some(charset)
+    }
+}
+
+/// 解码用的字符集：`encoding_rs` 的编码，或者它不支持的 UTF-32
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    Standard(&'static Encoding),
+    Utf32Le,
+    Utf32Be,
+}
+
+impl Charset {
+    /// 按编码名查找，支持 `encoding_rs` 的所有名字和 `UTF-32`/`UTF-32LE`/`UTF-32BE`
+    pub fn for_label(label: &str) -> Option<Charset> {
+        match label.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "utf-32" | "utf32" | "utf-32le" | "utf32le" => Some(Charset::Utf32Le),
+            "utf-32be" | "utf32be" => Some(Charset::Utf32Be),
+            _ => Encoding::for_label(label.trim().as_bytes()).map(Charset::Standard),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Charset::Standard(encoding) => encoding.name(),
+            Charset::Utf32Le => "UTF-32LE",
+            Charset::Utf32Be => "UTF-32BE",
+        }
+    }
+
+    /// 新的解码器，开头和编码一致的 BOM 会去掉
+    fn new_decoder(self) -> TextDecoder {
+        match self {
+            Charset::Standard(encoding) => {
+                TextDecoder::Standard(encoding.new_decoder_with_bom_removal())
+            }
+            Charset::Utf32Le => TextDecoder::Utf32(Utf32Decoder::new(false)),
+            Charset::Utf32Be => TextDecoder::Utf32(Utf32Decoder::new(true)),
+        }
+    }
+}
+
+impl From<&'static Encoding> for Charset {
+    fn from(encoding: &'static Encoding) -> Self {
+        Charset::Standard(encoding)
+    }
+}
+
+/// 按文件开头的 BOM 判断编码，返回编码和 BOM 的长度
+pub fn sniff_bom(head: &[u8]) -> Option<(Charset, usize)> {
+    // UTF-32LE 的 BOM 以 UTF-16LE 的 BOM 开头，要先判断
+    if head.starts_with(&[0xFF, 0xFE, 0x00, 0x00]) {
+        Some((Charset::Utf32Le, 4))
+    } else if head.starts_with(&[0x00, 0x00, 0xFE, 0xFF]) {
+        Some((Charset::Utf32Be, 4))
+    } else {
+        Encoding::for_bom(head).map(|(encoding, len)| (Charset::Standard(encoding), len))
+    }
+}
+
+/// 和 `encoding_rs::Decoder` 一样的接口，UTF-32 用自己的实现
+enum TextDecoder {
+    Standard(Decoder),
+    Utf32(Utf32Decoder),
+}
+
+impl TextDecoder {
+    /// 遇到无法解码的字节时返回 `Malformed`，由调用的地方决定怎么处理
+    fn decode(&mut self, src: &[u8], dst: &mut [u8], last: bool) -> (DecoderResult, usize, usize) {
+        match self {
+            TextDecoder::Standard(decoder) => {
+                decoder.decode_to_utf8_without_replacement(src, dst, last)
+            }
+            TextDecoder::Utf32(decoder) => decoder.decode(src, dst, last),
+        }
+    }
+}
+
+/// UTF-32 解码，码元可能被分在两次输入里
+struct Utf32Decoder {
+    big_endian: bool,
+    /// 第一个码元还没处理，是 BOM 的话去掉
+    at_start: bool,
+    unit: [u8; 4],
+    unit_len: usize,
+}
+
+impl Utf32Decoder {
+    fn new(big_endian: bool) -> Self {
+        Utf32Decoder {
+            big_endian,
+            at_start: true,
+            unit: [0; 4],
+            unit_len: 0,
+        }
+    }
+
+    /// 返回值的含义和 `Decoder::decode_to_utf8_without_replacement` 相同
+    fn decode(&mut self, src: &[u8], dst: &mut [u8], last: bool) -> (DecoderResult, usize, usize) {
+        let mut read = 0;
+        let mut written = 0;
+        loop {
+            while self.unit_len < 4 && read < src.len() {
+                self.unit[self.unit_len] = src[read];
+                self.unit_len += 1;
+                read += 1;
+            }
+            if self.unit_len < 4 {
+                // 结尾不够一个码元
+                if last && self.unit_len > 0 {
+                    let bad = self.unit_len as u8;
+                    self.unit_len = 0;
+                    return (DecoderResult::Malformed(bad, 0), read, written);
+                }
+                return (DecoderResult::InputEmpty, read, written);
+            }
+            let unit = if self.big_endian {
+                u32::from_be_bytes(self.unit)
+            } else {
+                u32::from_le_bytes(self.unit)
+            };
+            if std::mem::take(&mut self.at_start) && unit == 0xFEFF {
+                self.unit_len = 0;
+                continue;
+            }
+            // 代理项和超出 0x10FFFF 的值都不是字符
+            let Some(c) = char::from_u32(unit) else {
+                self.unit_len = 0;
+                return (DecoderResult::Malformed(4, 0), read, written);
+            };
+            if dst.len() - written < c.len_utf8() {
+                return (DecoderResult::OutputFull, read, written);
+            }
+            written += c.encode_utf8(&mut dst[written..]).len();
+            self.unit_len = 0;
+        }
+    }
+}
+
+/// 猜测编码时最多读取的字节数，大文件只看开头
+const DETECT_SAMPLE: u64 = 1 << 20;
+
+/// 每次解码的输入大小
+const DECODE_CHUNK: usize = 64 * 1024;
+
+/// 猜测文件编码，返回可信度最高的编码名
+pub fn detect_encoding(path: &Path) -> Option<String> {
+    let (file, _) = fs::open(path).ok()?;
+    let mut bytes = Vec::new();
+    file.take(DETECT_SAMPLE).read_to_end(&mut bytes).ok()?;
+    let result = charset_normalizer_rs::from_bytes(&bytes, None).ok()?;
+    result
+        .get_best()
+        .map(|best| best.encoding().to_uppercase().to_string())
+}
+
+/// 编码检查时读取的字节数
+const INSPECT_SAMPLE: u64 = 64 * 1024;
+
+/// 编码预览里的一段，无法解码的字节单独列出
+#[derive(Debug, Clone, PartialEq)]
+pub enum PreviewSpan {
+    Text(String),
+    Invalid(Vec<u8>),
+}
+
+/// [`inspect`] 找到的候选编码
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub encoding: Charset,
+    /// 0 ~ 100，由 charset_normalizer 的混乱度换算，有 BOM 时为 100
+    pub confidence: f32,
+    /// 文件开头几行的解码结果
+    pub preview: Vec<PreviewSpan>,
+}
+
+/// 列出最可能的 `limit` 个编码，每个带上开头 `lines` 行的预览
+pub fn inspect(path: &Path, limit: usize, lines: usize) -> Result<Vec<Candidate>> {
+    let (file, _) = fs::open(path)?;
+    let mut bytes = Vec::new();
+    file.take(INSPECT_SAMPLE).read_to_end(&mut bytes)?;
+
+    let mut candidates: Vec<Candidate> = Vec::new();
+    if let Some((encoding, _)) = sniff_bom(&bytes) {
+        candidates.push(Candidate {
+            encoding,
+            confidence: 100.0,
+            preview: preview(&bytes, encoding, lines),
+        });
+    }
+    // 没有任何结果（比如空文件）时只有 BOM 的结果
+    let Ok(matches) = charset_normalizer_rs::from_bytes(&bytes, None) else {
+        return Ok(candidates);
+    };
+    for found in matches.iter() {
+        if candidates.len() >= limit {
+            break;
+        }
+        let Some(encoding) = Charset::for_label(found.encoding()) else {
+            continue;
+        };
+        // 几个名字可能对应同一个编码，只保留分数最高的
+        if candidates.iter().any(|c| c.encoding == encoding) {
+            continue;
+        }
+        candidates.push(Candidate {
+            encoding,
+            confidence: (1.0 - found.chaos()).clamp(0.0, 1.0) * 100.0,
+            preview: preview(&bytes, encoding, lines),
+        });
+    }
+    Ok(candidates)
+}
+
+/// 按 `encoding` 解码 `bytes` 开头的 `lines` 行，标出无法解码的字节
+pub fn preview(bytes: &[u8], encoding: Charset, lines: usize) -> Vec<PreviewSpan> {
+    let mut decoder = encoding.new_decoder();
+    let mut spans = Vec::new();
+    let mut output = vec![0; bytes.len() * 3 + 16];
+    let mut src = bytes;
+    let mut newlines = 0;
+    loop {
+        // 样本可能在字符中间截断，结尾不完整的字节不算错误
+        let (result, read, written) = decoder.decode(src, &mut output, false);
+        let (start, end) = match result {
+            DecoderResult::InputEmpty | DecoderResult::OutputFull => (read, read),
+            DecoderResult::Malformed(bad, extra) => {
+                let end = read.saturating_sub(extra as usize);
+                (end.saturating_sub(bad as usize), end)
+            }
+        };
+        let mut text = String::from_utf8_lossy(&output[..written]).into_owned();
+        // 只保留前 `lines` 行
+        if let Some(cut) = text
+            .match_indices('\n')
+            .nth(lines.saturating_sub(newlines + 1))
+            .map(|(i, _)| i)
+        {
+            text.truncate(cut);
+            push_text(&mut spans, &text);
+            return spans;
+        }
+        newlines += text.matches('\n').count();
+        push_text(&mut spans, &text);
+        if start == end {
+            if result == DecoderResult::OutputFull {
+                src = &src[read..];
+                continue;
+            }
+            return spans;
+        }
+        spans.push(PreviewSpan::Invalid(src[start..end].to_vec()));
+        src = &src[read..];
+    }
+}
+
+fn push_text(spans: &mut Vec<PreviewSpan>, text: &str) {
+    let text = text.replace('\r', "");
+    if !text.is_empty() {
+        spans.push(PreviewSpan::Text(text));
+    }
+}
+
+/// 读取文件开头用来判断 BOM 的几个字节
+fn read_head(path: &Path) -> Option<Vec<u8>> {
+    let (file, _) = fs::open(path).ok()?;
+    let mut head = Vec::with_capacity(4);
+    file.take(4).read_to_end(&mut head).ok()?;
+    Some(head)
+}
+
+/// 按选择的编码确定解码方式
+///
+/// 文件开头有 BOM 时按 BOM 解码。没有 BOM 时 `UTF8` 返回 `None` 不做处理，`Other` 会自己猜测编码。
+pub fn resolve(path: &Path, encode: &FileEncoding) -> Option<Charset> {
+    if let Some((charset, _)) = read_head(path).and_then(|head| sniff_bom(&head)) {
+        log::info!("{}: decode as {} (BOM)", path.display(), charset.name());
+        return Some(charset);
+    }
+    let charset = match encode {
+        FileEncoding::UTF8 => return None,
+        FileEncoding::Other => {
+            let name = detect_encoding(path)?;
+            Charset::for_label(&name).unwrap_or(Charset::Standard(encoding_rs::UTF_8))
+        }
+        _ => encode.charset().unwrap_or_else(|| {
+            log::warn!("unknown encoding {}, decode as UTF-8", encode.name());
+            Charset::Standard(encoding_rs::UTF_8)
+        }),
+    };
+    log::info!("{}: decode as {}", path.display(), charset.name());
+    Some(charset)
+}
+
+/// 无法解码的一段字节在输入里的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeError {
+    /// 从文件开头算起的字节偏移
+    pub offset: u64,
+    pub len: usize,
+}
+
+/// [`DecodeReader`] 最多记下的错误位置，之后只计数
+const MAX_ERRORS: usize = 16;
+
+/// U+FFFD 的 utf8 编码
+const REPLACEMENT: &[u8] = "\u{FFFD}".as_bytes();
+
+/// 读取时把内容解码成 utf8，`encoding` 为 `None` 时原样读取
+///
+/// 无法解码的字节替换成 U+FFFD，读完后可以用 [`DecodeReader::errors`] 查看位置。
+pub struct DecodeReader<R> {
+    inner: R,
+    decoder: Option<TextDecoder>,
+    input: Vec<u8>,
+    input_pos: usize,
+    input_len: usize,
+    /// 解码器已经处理的输入字节数
+    consumed: u64,
+    output: Vec<u8>,
+    output_pos: usize,
+    output_len: usize,
+    /// 已经读到 `inner` 的结尾
+    eof: bool,
+    finished: bool,
+    errors: Vec<DecodeError>,
+    error_count: u64,
+}
+
+impl<R: Read> DecodeReader<R> {
+    pub fn new(inner: R, encoding: Option<Charset>) -> Self {
+        let decoder = encoding.map(Charset::new_decoder);
+        let (input, output) = if decoder.is_some() {
+            (vec![0; DECODE_CHUNK], vec![0; DECODE_CHUNK * 3 + 16])
+        } else {
+            (Vec::new(), Vec::new())
+        };
+        DecodeReader {
+            inner,
+            decoder,
+            input,
+            input_pos: 0,
+            input_len: 0,
+            consumed: 0,
+            output,
+            output_pos: 0,
+            output_len: 0,
+            eof: false,
+            finished: false,
+            errors: Vec::new(),
+            error_count: 0,
+        }
+    }
+
+    /// 解码下一块，返回 false 表示已经全部读完
+    fn fill_output(&mut self, decoder: &mut TextDecoder) -> io::Result<bool> {
+        self.output_pos = 0;
+        self.output_len = 0;
+        while !self.finished {
+            if self.input_pos == self.input_len && !self.eof {
+                self.input_len = self.inner.read(&mut self.input)?;
+                self.input_pos = 0;
+                self.eof = self.input_len == 0;
+            }
+            let (result, read, written) = decoder.decode(
+                &self.input[self.input_pos..self.input_len],
+                &mut self.output,
+                self.eof,
+            );
+            self.input_pos += read;
+            self.consumed += read as u64;
+            self.output_len = written;
+            match result {
+                DecoderResult::InputEmpty => self.finished = self.eof,
+                DecoderResult::OutputFull => {}
+                DecoderResult::Malformed(bad, extra) => {
+                    let end = self.consumed - extra as u64;
+                    self.record_error(end.saturating_sub(bad as u64), bad as usize);
+                    // 输出缓冲区比一块输入解码后的最大长度还多 16 字节，放得下
+                    self.output[written..written + REPLACEMENT.len()].copy_from_slice(REPLACEMENT);
+                    self.output_len += REPLACEMENT.len();
+                }
+            }
+            if self.output_len > 0 {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn record_error(&mut self, offset: u64, len: usize) {
+        self.error_count += 1;
+        if self.errors.len() < MAX_ERRORS {
+            self.errors.push(DecodeError { offset, len });
+        }
+    }
+}
+
+impl<R> DecodeReader<R> {
+    /// 是否遇到过无法解码的字节
+    pub fn had_errors(&self) -> bool {
+        self.error_count > 0
+    }
+
+    /// 前几处无法解码的位置，总数见 [`DecodeReader::error_count`]
+    pub fn errors(&self) -> &[DecodeError] {
+        &self.errors
+    }
+
+    pub fn error_count(&self) -> u64 {
+        self.error_count
+    }
+}
+
+impl<R: Read> Read for DecodeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(mut decoder) = self.decoder.take() else {
+            return self.inner.read(buf);
+        };
+        let filled = if self.output_pos < self.output_len {
+            Ok(true)
+        } else {
+            self.fill_output(&mut decoder)
+        };
+        self.decoder = Some(decoder);
+        if !filled? {
+            return Ok(0);
+        }
+        let n = buf.len().min(self.output_len - self.output_pos);
+        buf[..n].copy_from_slice(&self.output[self.output_pos..self.output_pos + n]);
+        self.output_pos += n;
+        Ok(n)
+    }
+}
+
+/// 打开输入文件，按 `encode` 解码成 utf8，原文件不变
+pub fn open(
+    path: &Path,
+    encode: &FileEncoding,
+    progress: &Progress,
+) -> Result<DecodeReader<ProgressReader<fs::Reader>>> {
+    let encoding = resolve(path, encode);
+    Ok(DecodeReader::new(progress.open(path)?, encoding))
+}
+
+/// 解码出错时提示一下出错的字节偏移，不影响处理
+pub fn warn_errors<R>(path: &Path, reader: &DecodeReader<R>) {
+    if !reader.had_errors() {
+        return;
+    }
+    let mut offsets: Vec<String> = reader
+        .errors()
+        .iter()
+        .map(|error| error.offset.to_string())
+        .collect();
+    if reader.error_count() > offsets.len() as u64 {
+        offsets.push("...".to_string());
+    }
+    log::warn!(
+        "{}: {} byte sequence(s) could not be decoded, at byte offset {}",
+        path.display(),
+        reader.error_count(),
+        offsets.join(", ")
+    );
+}
+
+/// 分块把 `reader` 按 `encoding` 解码成 utf8 写到 `writer`，返回是否有无法解码的字符
+pub fn decode_stream<R: Read, W: Write>(
+    encoding: Charset,
+    reader: R,
+    writer: &mut W,
+) -> Result<bool> {
+    let mut reader = DecodeReader::new(reader, Some(encoding));
+    io::copy(&mut reader, writer)?;
+    Ok(reader.had_errors())
+}
+
+/// [`save_normalized`] 的输出格式
+pub const NORMALIZED_FORMAT: &str = "utf8";
+
+/// 把解码后的内容另存一份，输出格式为 `utf8`，原文件不变
+pub fn save_normalized(
+    path: &Path,
+    encode: &FileEncoding,
+    output: &OutputPolicy,
+    progress: &Progress,
+) -> Result<PathBuf> {
+    let out = output.resolve(path, NORMALIZED_FORMAT)?;
+    let mut reader = open(path, encode, progress)?;
+    fs::write_atomic(&out, |writer| io::copy(&mut reader, writer))?;
+    warn_errors(path, &reader);
+    log::info!("{}: saved utf8 copy {}", path.display(), out.display());
+    Ok(out)
+}
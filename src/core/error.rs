@@ -0,0 +1,114 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use super::progress::Cancelled;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// 处理过程中可能出现的错误，行号都是从 1 开始的文件行号
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Csv(csv::Error),
+    /// 第一行不是期望的表头，一般是选错了协议或者导出格式
+    InvalidHeader {
+        expected: &'static str,
+    },
+    /// 缺少必须的列
+    MissingColumn(&'static str),
+    /// 某一行的数据无法解析
+    InvalidValue {
+        line: usize,
+        content: String,
+    },
+    /// 文件内容不是合法的 utf8，需要先转换编码
+    NotUtf8,
+    /// 被 [`super::Progress::cancel`] 取消
+    Cancelled,
+    /// 输出文件已经存在，按 [`super::Conflict::Skip`] 跳过
+    Skipped(PathBuf),
+    /// 输出路径和输入文件相同，需要修改输出模板或目录
+    OutputIsInput(PathBuf),
+    /// 渲染出的输出文件名带着目录或者 `..`，会写到输出目录外面
+    InvalidOutputName(String),
+    /// 模板里没有 `{format}`，一次处理的几个输出会写到同一个文件
+    SameOutputName(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Csv(e) => write!(f, "{}", e),
+            Error::InvalidHeader { expected } => {
+                write!(f, "unexpected file header, expected \"{}\"", expected)
+            }
+            Error::MissingColumn(column) => write!(f, "missing '{}' column", column),
+            Error::InvalidValue { line, content } => {
+                write!(f, "line {}: invalid value in \"{}\"", line, content)
+            }
+            Error::NotUtf8 => write!(f, "file is not valid utf8, select the input encoding"),
+            Error::Cancelled => write!(f, "cancelled"),
+            Error::Skipped(path) => write!(f, "skipped, {} already exists", path.display()),
+            Error::OutputIsInput(path) => {
+                write!(f, "output {} would overwrite the input", path.display())
+            }
+            Error::InvalidOutputName(name) => {
+                write!(f, "output name \"{}\" must be a single file name", name)
+            }
+            Error::SameOutputName(template) => write!(
+                f,
+                "output name template \"{}\" gives every output the same name, add {{format}}",
+                template
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Csv(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+fn is_cancelled(e: &io::Error) -> bool {
+    e.get_ref().is_some_and(|inner| inner.is::<Cancelled>())
+}
+
+/// 包着 utf8 解码错误的 io 错误
+///
+/// [`super::encoding::DecodeReader`] 把无法解码的字节换成 U+FFFD，不会出错；按 utf8 读取的地方
+/// 自己返回 [`Error::NotUtf8`]。其他的 `InvalidData` 和编码无关，不能提示用户去选编码。
+fn is_not_utf8(e: &io::Error) -> bool {
+    e.get_ref().is_some_and(|inner| {
+        inner.is::<std::str::Utf8Error>() || inner.is::<std::string::FromUtf8Error>()
+    })
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        if is_cancelled(&e) {
+            return Error::Cancelled;
+        }
+        if is_not_utf8(&e) {
+            return Error::NotUtf8;
+        }
+        Error::Io(e)
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(e: csv::Error) -> Self {
+        if let csv::ErrorKind::Io(io) = e.kind() {
+            if is_cancelled(io) {
+                return Error::Cancelled;
+            }
+        }
+        Error::Csv(e)
+    }
+}
@@ -0,0 +1,219 @@
+//! 处理函数用到的文件读写
+//!
+//! 本地直接使用 `std::fs`。网页版没有文件系统，拖入的文件用 [`insert`] 放进内存，
+//! 路径就是文件名；写出的文件在关闭时存回内存，页面再用 [`read`] 取出来给浏览器下载。
+
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod imp {
+    use std::fs::{self, File};
+    use std::io;
+    use std::path::Path;
+
+    pub type Reader = File;
+    pub type Writer = File;
+
+    pub fn open(path: &Path) -> io::Result<(Reader, u64)> {
+        let file = File::open(path)?;
+        let len = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+        Ok((file, len))
+    }
+
+    pub fn create(path: &Path) -> io::Result<Writer> {
+        File::create(path)
+    }
+
+    pub fn is_file(path: &Path) -> bool {
+        path.is_file()
+    }
+
+    /// 文件大小，打不开时为 0
+    pub fn file_len(path: &Path) -> u64 {
+        fs::metadata(path).map(|meta| meta.len()).unwrap_or(0)
+    }
+
+    pub fn exists(path: &Path) -> bool {
+        path.exists()
+    }
+
+    pub fn create_dir_all(dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)
+    }
+
+    pub fn rename(from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    pub fn remove_file(path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    /// 写法不同的路径（相对路径、`..`、符号链接）也能认出是同一个文件
+    pub fn same_file(a: &Path, b: &Path) -> bool {
+        a == b || matches!((a.canonicalize(), b.canonicalize()), (Ok(a), Ok(b)) if a == b)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod imp {
+    use std::collections::HashMap;
+    use std::io::{self, Cursor, Write};
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+
+    use once_cell::sync::Lazy;
+
+    static FILES: Lazy<Mutex<HashMap<PathBuf, Arc<[u8]>>>> = Lazy::new(Default::default);
+
+    fn files() -> std::sync::MutexGuard<'static, HashMap<PathBuf, Arc<[u8]>>> {
+        FILES.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn not_found(path: &Path) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} is not loaded", path.display()),
+        )
+    }
+
+    pub type Reader = Cursor<Arc<[u8]>>;
+
+    /// 写到内存里，drop 时替换同名文件
+    pub struct Writer {
+        path: PathBuf,
+        buf: Vec<u8>,
+    }
+
+    impl Write for Writer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.buf.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Drop for Writer {
+        fn drop(&mut self) {
+            let buf = std::mem::take(&mut self.buf);
+            files().insert(std::mem::take(&mut self.path), buf.into());
+        }
+    }
+
+    pub fn insert(path: PathBuf, bytes: Arc<[u8]>) {
+        files().insert(path, bytes);
+    }
+
+    pub fn open(path: &Path) -> io::Result<(Reader, u64)> {
+        let bytes = files().get(path).cloned().ok_or_else(|| not_found(path))?;
+        let len = bytes.len() as u64;
+        Ok((Cursor::new(bytes), len))
+    }
+
+    pub fn create(path: &Path) -> io::Result<Writer> {
+        Ok(Writer {
+            path: path.to_path_buf(),
+            buf: Vec::new(),
+        })
+    }
+
+    pub fn is_file(path: &Path) -> bool {
+        files().contains_key(path)
+    }
+
+    pub fn file_len(path: &Path) -> u64 {
+        files().get(path).map_or(0, |bytes| bytes.len() as u64)
+    }
+
+    pub fn exists(path: &Path) -> bool {
+        is_file(path)
+    }
+
+    pub fn create_dir_all(_dir: &Path) -> io::Result<()> {
+        Ok(())
+    }
+
+    pub fn rename(from: &Path, to: &Path) -> io::Result<()> {
+        let mut files = files();
+        let bytes = files.remove(from).ok_or_else(|| not_found(from))?;
+        files.insert(to.to_path_buf(), bytes);
+        Ok(())
+    }
+
+    pub fn remove_file(path: &Path) -> io::Result<()> {
+        files()
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| not_found(path))
+    }
+
+    pub fn same_file(a: &Path, b: &Path) -> bool {
+        a == b
+    }
+}
+
+pub use imp::*;
+
+pub fn read(path: &Path) -> io::Result<Vec<u8>> {
+    let (mut reader, len) = open(path)?;
+    let mut buf = Vec::with_capacity(len as usize);
+    io::Read::read_to_end(&mut reader, &mut buf)?;
+    Ok(buf)
+}
+
+pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    io::Write::write_all(&mut create(path)?, contents.as_ref())
+}
+
+/// 输出文件的写缓冲区大小
+const BUF_SIZE: usize = 128 * 1024;
+
+/// 和 [`write_atomic_all`] 一样，只写一个文件
+pub fn write_atomic<T, E: From<io::Error>>(
+    path: &Path,
+    f: impl FnOnce(&mut BufWriter<Writer>) -> Result<T, E>,
+) -> Result<T, E> {
+    write_atomic_all([path], |[writer]| f(writer))
+}
+
+/// 先写到 `paths` 旁边的 `.part` 文件，`f` 成功后再换成 `paths`
+///
+/// `f` 出错或者被取消时删掉 `.part` 文件，不会留下空的或者写了一半的输出，
+/// 也不会因为 [`super::Conflict::Skip`] 在重试时跳过它们。
+pub fn write_atomic_all<const N: usize, T, E: From<io::Error>>(
+    paths: [&Path; N],
+    f: impl FnOnce(&mut [BufWriter<Writer>; N]) -> Result<T, E>,
+) -> Result<T, E> {
+    let parts = paths.map(|path| {
+        let mut part = path.as_os_str().to_owned();
+        part.push(".part");
+        PathBuf::from(part)
+    });
+    let write = || -> Result<T, E> {
+        let mut writers = Vec::with_capacity(N);
+        for part in &parts {
+            writers.push(BufWriter::with_capacity(BUF_SIZE, create(part)?));
+        }
+        let mut writers: [_; N] = writers.try_into().unwrap_or_else(|_| unreachable!());
+        let value = f(&mut writers)?;
+        for writer in writers {
+            // 网页版在 drop 时才存回内存，要在改名前关掉
+            let mut writer = writer
+                .into_inner()
+                .map_err(io::IntoInnerError::into_error)?;
+            writer.flush()?;
+        }
+        for (part, path) in parts.iter().zip(paths) {
+            rename(part, path)?;
+        }
+        Ok(value)
+    };
+    write().inspect_err(|_| {
+        for part in &parts {
+            remove_file(part).ok();
+        }
+    })
+}
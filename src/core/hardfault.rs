@@ -0,0 +1,198 @@
+//! hardfault / 看门狗复位时打印的寄存器解析
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use super::encoding::{self, FileEncoding};
+use super::{fs, Error, OutputPolicy, Progress, Result};
+
+#[derive(Debug, Default, Serialize, Clone, PartialEq)]
+pub struct CPURegs {
+    pub regs: [String; 32],
+    pub header: String,
+}
+
+pub static REG_NAME: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+impl CPURegs {
+    pub fn display(&self) -> String {
+        let mut ret = String::new();
+        ret.push_str(&format!("{}\n", self.header));
+        for (i, reg) in self.regs.iter().enumerate() {
+            if i > 0 && (i % 4 == 0) {
+                ret.push('\n');
+            }
+            ret.push_str(&format!("{}: {}, ", REG_NAME[i], reg));
+        }
+        ret
+    }
+}
+
+/// 看门狗复位时没有打印的寄存器
+const EMPTY_REG: &str = "0xXXXXXXXX";
+
+/// 逐行解析的状态
+#[derive(Default)]
+struct Parser {
+    regs: CPURegs,
+    reg_vec: Vec<CPURegs>,
+    index: usize,
+    /// 1: epc, 2: wdt
+    state: u8,
+}
+
+impl Parser {
+    /// `line` 不含换行符，`\r` 和 `\n` 都算作换行
+    fn line(&mut self, line: &str) {
+        match self.state {
+            1 => {
+                for l in line.split(' ') {
+                    if l.is_empty() {
+                        continue;
+                    }
+                    // 多出来的数值不属于寄存器组
+                    if self.index >= REG_NAME.len() {
+                        break;
+                    }
+                    if let Ok(reg) = u32::from_str_radix(l, 16) {
+                        self.regs.regs[self.index] = format!("{:#010X}", reg);
+                    } else {
+                        self.state = 3;
+                    }
+
+                    self.index += 1;
+                }
+                if self.index >= 32 {
+                    self.state = 3;
+                    self.reg_vec.push(self.regs.clone());
+                }
+            }
+            2 => {
+                for l in line.split(' ') {
+                    match self.index {
+                        0 => {
+                            self.regs.regs[self.index] = EMPTY_REG.to_string();
+                            self.index += 1;
+                        }
+                        2 => {
+                            while self.index < 4 {
+                                self.regs.regs[self.index] = EMPTY_REG.to_string();
+                                self.index += 1;
+                            }
+                        }
+                        18 => {
+                            while self.index < 28 {
+                                self.regs.regs[self.index] = EMPTY_REG.to_string();
+                                self.index += 1;
+                            }
+                        }
+                        _ => {}
+                    }
+                    if l.is_empty() {
+                        continue;
+                    }
+                    if self.index >= REG_NAME.len() {
+                        break;
+                    }
+
+                    if let Ok(reg) = u32::from_str_radix(l, 16) {
+                        self.regs.regs[self.index] = format!("{:#010X}", reg);
+                    } else {
+                        self.state = 3;
+                    }
+
+                    self.index += 1;
+                }
+                if self.index >= 19 {
+                    self.state = 3;
+                    self.reg_vec.push(self.regs.clone());
+                }
+            }
+            _ => {}
+        }
+
+        if line.contains("ERR:") && line.contains("EPC:") {
+            self.regs.header = line.to_string();
+            self.state = 1;
+            self.index = 0;
+        }
+        if line.contains("WDT_RST:") {
+            self.regs.header = line.to_string();
+            self.state = 2;
+            self.index = 0;
+        }
+    }
+}
+
+/// 解析日志里所有的寄存器组，输入需要是 utf8
+///
+/// 逐行读取，不会把整个文件读进内存
+pub fn parse<R: Read>(reader: R) -> Result<Vec<CPURegs>> {
+    let mut reader = BufReader::new(reader);
+    let mut parser = Parser::default();
+    let mut buf = Vec::new();
+    // 和按 `\r`、`\n` 切分整个文件一样，最后一个换行后面还有一个空行
+    let mut ends_with_newline = true;
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            break;
+        }
+        ends_with_newline = buf.last() == Some(&b'\n');
+        if ends_with_newline {
+            buf.pop();
+        }
+        let lines = std::str::from_utf8(&buf).map_err(|_| Error::NotUtf8)?;
+        for line in lines.split('\r') {
+            parser.line(line);
+        }
+    }
+    if ends_with_newline {
+        parser.line("");
+    }
+
+    Ok(parser.reg_vec)
+}
+
+/// 按 `encode` 解码并解析文件，原文件不变
+pub fn parse_file(path: &Path, encode: &FileEncoding, progress: &Progress) -> Result<Vec<CPURegs>> {
+    let mut reader = encoding::open(path, encode, progress)?;
+    let regs = parse(&mut reader)?;
+    encoding::warn_errors(path, &reader);
+    log::info!("{}: {} register groups", path.display(), regs.len());
+    Ok(regs)
+}
+
+/// 把寄存器组按 [`CPURegs::display`] 的格式写出
+pub fn write_report<W: Write>(regs: &[CPURegs], mut writer: W) -> Result<()> {
+    for reg in regs {
+        writeln!(writer, "{}\n", reg.display())?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// 寄存器报告的输出格式
+pub const REPORT_FORMAT: &str = "regs.txt";
+
+/// 按 `output` 写出格式为 `regs.txt` 的报告，没有寄存器组时不写
+///
+/// 报告里的寄存器行不是纯十六进制，重新解析报告不会得到寄存器组
+pub fn report_file(
+    path: &Path,
+    regs: &[CPURegs],
+    output: &OutputPolicy,
+) -> Result<Option<PathBuf>> {
+    if regs.is_empty() {
+        return Ok(None);
+    }
+    let out = output.resolve(path, REPORT_FORMAT)?;
+    fs::write_atomic(&out, |writer| write_report(regs, writer))?;
+    Ok(Some(out))
+}
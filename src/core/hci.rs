@@ -0,0 +1,198 @@
+//! hci 文本日志转换成 btsnoop 文件（HCI UART H4），可以直接用 Wireshark 打开
+//!
+//! 日志里 `[00:00:01.100]CMD => 01 03 0C 00` 这样的记录转换成一个包，`=>` 是主机发给控制器，
+//! `<=` 是控制器发给主机。一行里可以有多条记录，其他内容（调试打印、中文等）都忽略。
+
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::encoding::{self, FileEncoding};
+use super::{fs, Error, OutputPolicy, Progress, Result};
+
+static RECORD_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"\[(\d{2}):(\d{2}):(\d{2})\.(\d{3})\]\s*",
+        // 有的日志在包类型前面还有一个 `(时:分:秒.毫秒)` 时间戳，忽略它
+        r"(?:\(\d{2}:\d{2}:\d{2}\.\d{3}\)\s*)?",
+        r"(CMD|EVT|ACL) (=>|<=)((?:\s+[0-9A-Fa-f]{2}\b)+)",
+    ))
+    .unwrap()
+});
+
+/// btsnoop 文件头，版本 1，数据链路类型 1002（HCI UART H4）
+const BTSNOOP_HEADER: [u8; 16] = *b"btsnoop\0\x00\x00\x00\x01\x00\x00\x03\xea";
+
+/// btsnoop 的时间从公元 0 年开始，日志里只有时分秒，按 1970-01-01 当天写
+const BTSNOOP_UNIX_EPOCH: u64 = 0x00dc_ddb3_0f2f_8000;
+
+const DAY_US: u64 = 24 * 60 * 60 * 1_000_000;
+
+/// H4 包类型，值是包的第一个字节
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketType {
+    Command = 0x01,
+    Acl = 0x02,
+    Event = 0x04,
+}
+
+impl PacketType {
+    fn from_name(name: &str) -> Self {
+        match name {
+            "CMD" => PacketType::Command,
+            "ACL" => PacketType::Acl,
+            _ => PacketType::Event,
+        }
+    }
+
+    /// 按包头计算的长度，`data` 不带包类型
+    fn packet_len(self, data: &[u8]) -> Option<usize> {
+        match self {
+            PacketType::Command => data.get(2).map(|&len| 3 + len as usize),
+            PacketType::Acl => data
+                .get(2..4)
+                .map(|len| 4 + u16::from_le_bytes([len[0], len[1]]) as usize),
+            PacketType::Event => data.get(1).map(|&len| 2 + len as usize),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packet {
+    pub packet_type: PacketType,
+    /// 控制器发给主机（`<=`）
+    pub received: bool,
+    /// 从日志第一天零点开始的微秒数
+    pub time: u64,
+    /// 包括第一个字节的包类型
+    pub data: Vec<u8>,
+}
+
+/// 日志里的数据一般带着包类型，没有时补上
+///
+/// 第一个字节碰巧等于包类型时（比如 opcode 0x0401），看哪种解释和包头里的长度对得上。
+fn has_indicator(packet_type: PacketType, data: &[u8]) -> bool {
+    if data.first() != Some(&(packet_type as u8)) {
+        return false;
+    }
+    let fits = |data: &[u8]| packet_type.packet_len(data) == Some(data.len());
+    fits(&data[1..]) || !fits(data)
+}
+
+/// 逐行解析记录，时间倒退超过半天时认为过了零点
+#[derive(Default)]
+pub struct Parser {
+    last_time: u64,
+    day: u64,
+}
+
+impl Parser {
+    /// 解析一行（可以带着换行符），找到的包追加到 `packets`
+    pub fn line(&mut self, line: &str, packets: &mut Vec<Packet>) {
+        for caps in RECORD_RE.captures_iter(line) {
+            let field = |i: usize| caps[i].parse::<u64>().unwrap_or_default();
+            let mut time = (((field(1) * 60 + field(2)) * 60 + field(3)) * 1000 + field(4)) * 1000;
+            if time + DAY_US / 2 < self.last_time {
+                self.day += 1;
+            }
+            self.last_time = time;
+            time += self.day * DAY_US;
+
+            let packet_type = PacketType::from_name(&caps[5]);
+            let mut data: Vec<u8> = caps[7]
+                .split_whitespace()
+                .filter_map(|byte| u8::from_str_radix(byte, 16).ok())
+                .collect();
+            if !has_indicator(packet_type, &data) {
+                data.insert(0, packet_type as u8);
+            }
+            packets.push(Packet {
+                packet_type,
+                received: &caps[6] == "<=",
+                time,
+                data,
+            });
+        }
+    }
+}
+
+/// 解析整个日志
+pub fn parse(content: &str) -> Vec<Packet> {
+    let mut parser = Parser::default();
+    let mut packets = Vec::new();
+    for line in content.split_inclusive('\n') {
+        parser.line(line, &mut packets);
+    }
+    packets
+}
+
+/// 写文件头，之后每个包用 [`write_packet`] 写入
+pub fn write_header<W: Write>(writer: &mut W) -> Result<()> {
+    writer.write_all(&BTSNOOP_HEADER)?;
+    Ok(())
+}
+
+pub fn write_packet<W: Write>(writer: &mut W, packet: &Packet) -> Result<()> {
+    let len = packet.data.len() as u32;
+    // bit 0：方向，bit 1：命令或者事件
+    let mut flags = packet.received as u32;
+    if packet.packet_type != PacketType::Acl {
+        flags |= 2;
+    }
+    let mut record = [0; 24];
+    record[0..4].copy_from_slice(&len.to_be_bytes());
+    record[4..8].copy_from_slice(&len.to_be_bytes());
+    record[8..12].copy_from_slice(&flags.to_be_bytes());
+    // 12..16 是丢弃的包数，总是 0
+    record[16..24].copy_from_slice(&BTSNOOP_UNIX_EPOCH.saturating_add(packet.time).to_be_bytes());
+    writer.write_all(&record)?;
+    writer.write_all(&packet.data)?;
+    Ok(())
+}
+
+/// 逐行读取 `reader` 转换成 btsnoop 写到 `writer`，输入需要是 utf8，返回包的个数
+pub fn convert_stream<R: BufRead, W: Write>(mut reader: R, writer: &mut W) -> Result<usize> {
+    write_header(writer)?;
+    let mut parser = Parser::default();
+    let mut packets = Vec::new();
+    let mut count = 0;
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            break;
+        }
+        let line = std::str::from_utf8(&buf).map_err(|_| Error::NotUtf8)?;
+        packets.clear();
+        parser.line(line, &mut packets);
+        for packet in &packets {
+            write_packet(writer, packet)?;
+        }
+        count += packets.len();
+    }
+    Ok(count)
+}
+
+/// btsnoop 文件的输出格式
+pub const BTSNOOP_FORMAT: &str = "btsnoop";
+
+/// 按 `encode` 解码后把 `path` 转换成 btsnoop 文件（输出格式为 `btsnoop`），原文件不变
+pub fn convert_file(
+    path: &Path,
+    encode: &FileEncoding,
+    output: &OutputPolicy,
+    progress: &Progress,
+) -> Result<PathBuf> {
+    let out = output.resolve(path, BTSNOOP_FORMAT)?;
+    let mut reader = BufReader::new(encoding::open(path, encode, progress)?);
+    let count = fs::write_atomic(&out, |writer| convert_stream(&mut reader, writer))?;
+    encoding::warn_errors(path, reader.get_ref());
+    if count == 0 {
+        log::warn!("{}: no CMD/EVT/ACL records found", path.display());
+    } else {
+        log::info!("{}: {} packets", path.display(), count);
+    }
+    Ok(out)
+}
@@ -0,0 +1,275 @@
+//! Kingst 逻辑分析仪导出 csv 的转换
+
+use std::io::{BufRead, BufReader, Write};
+use std::num::ParseIntError;
+use std::path::{Path, PathBuf};
+
+use super::{fs, Error, OutputPolicy, Progress, ProgressReader, Result};
+
+#[derive(Copy, Clone, PartialEq, Debug, serde::Deserialize, serde::Serialize)]
+pub enum SpiConvType {
+    RAW,
+    BluetrumVoiceDump,
+    TXT,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, serde::Deserialize, serde::Serialize)]
+pub struct LogicSpiArgs {
+    pub conv_type: SpiConvType,
+}
+
+impl Default for LogicSpiArgs {
+    fn default() -> Self {
+        LogicSpiArgs {
+            conv_type: SpiConvType::RAW,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct LogicIISArgs {}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct LogicUARTArgs {}
+
+const KINGST_ERROR_STR: &str =
+    "The initial (idle) state of the CLK line does not match the settings";
+
+pub const KINGST_IIS_FILE_FORMAT: &str = "Time [s],Channel,Value";
+pub const KINGST_SPI_FILE_FORMAT: &str = "Time [s],Packet ID,MOSI,MISO";
+pub const KINGST_UART_FILE_FORMAT: &str = "Time [s],Value,Parity Error,Framing Error";
+
+/// 各种转换可能写出的输出格式
+pub const OUTPUT_FORMATS: &[&str] = &["out", "out1", "out2", "out3", "txt"];
+
+/// 检查表头后逐行回调 `(行号, 行内容)`，错误数据会被跳过
+///
+/// 每次只读一行并复用同一个缓冲区，多 GB 的导出文件也不会占用太多内存
+fn kingst_records<R: BufRead>(
+    mut reader: R,
+    header: &'static str,
+    mut f: impl FnMut(usize, &str) -> Result<()>,
+) -> Result<()> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 || !line.contains(header) {
+        return Err(Error::InvalidHeader { expected: header });
+    }
+
+    let mut lineno = 1;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        lineno += 1;
+        // 和 `BufRead::lines` 一样去掉换行符
+        let record = match line.strip_suffix('\n') {
+            Some(record) => record.strip_suffix('\r').unwrap_or(record),
+            None => &line,
+        };
+        // 跳过错误数据
+        if record.contains(KINGST_ERROR_STR) {
+            continue;
+        }
+        f(lineno, record)?;
+    }
+}
+
+/// 取出一行里 `0x` 开头的字段并解析
+fn kingst_value<T>(
+    lineno: usize,
+    line: &str,
+    from_str_radix: fn(&str, u32) -> std::result::Result<T, ParseIntError>,
+) -> Result<T> {
+    let mut fields = line.split(',').filter(|w| w.contains("0x"));
+    // 一般只有一个字段，不用拼接
+    let value = match (fields.next(), fields.next()) {
+        (Some(field), None) => from_str_radix(field.trim_start_matches("0x"), 16),
+        (first, second) => {
+            let data: String = first.into_iter().chain(second).chain(fields).collect();
+            from_str_radix(data.trim_start_matches("0x"), 16)
+        }
+    };
+    value.map_err(|_| Error::InvalidValue {
+        line: lineno,
+        content: line.to_string(),
+    })
+}
+
+/// 每 16 个字节一行的十六进制文本
+fn write_hex_txt<R: BufRead, W: Write>(reader: R, header: &'static str, out: &mut W) -> Result<()> {
+    let mut cnt: usize = 0;
+    kingst_records(reader, header, |lineno, line| {
+        let data = kingst_value(lineno, line, u8::from_str_radix)?;
+        if cnt > 0 && (cnt % 16 == 0) {
+            writeln!(out)?;
+        }
+        write!(out, "{:02x} ", data)?;
+        cnt += 1;
+        Ok(())
+    })
+}
+
+/// IIS 数据，每个采样按 u16 小端写出
+pub fn iis<R: BufRead, W: Write>(_args: &LogicIISArgs, reader: R, out: &mut W) -> Result<()> {
+    kingst_records(reader, KINGST_IIS_FILE_FORMAT, |lineno, line| {
+        let data = kingst_value(lineno, line, u16::from_str_radix)?;
+        out.write_all(&data.to_le_bytes())?;
+        Ok(())
+    })
+}
+
+/// MOSI 数据按原始字节写出
+pub fn spi_raw<R: BufRead, W: Write>(reader: R, out: &mut W) -> Result<()> {
+    kingst_records(reader, KINGST_SPI_FILE_FORMAT, |lineno, line| {
+        let data = kingst_value(lineno, line, u8::from_str_radix)?;
+        out.write_all(&[data])?;
+        Ok(())
+    })
+}
+
+#[derive(Debug, Default)]
+struct BluetrumVoiceDump {
+    version: [u8; 4],
+    frame_type: u8,
+    len: u16,
+    frame_num: u8,
+}
+
+enum BluetrumVoiceDumpState {
+    Header,
+    Body,
+}
+
+/// 蓝讯音频 DUMP 格式，按帧类型 0/2/4 分别写到 `outs[0]`/`outs[1]`/`outs[2]`
+pub fn spi_bluetrum<R: BufRead, W: Write>(reader: R, outs: &mut [W; 3]) -> Result<()> {
+    let mut cnt = 0;
+    let mut header = BluetrumVoiceDump::default();
+    let mut header_cache = Vec::new();
+    let mut state = BluetrumVoiceDumpState::Header;
+    kingst_records(reader, KINGST_SPI_FILE_FORMAT, |lineno, line| {
+        let data = kingst_value(lineno, line, u8::from_str_radix)?;
+
+        match state {
+            BluetrumVoiceDumpState::Header => {
+                header_cache.push(data);
+                if header_cache.len() == 8 {
+                    header = BluetrumVoiceDump {
+                        version: [
+                            header_cache[0],
+                            header_cache[1],
+                            header_cache[2],
+                            header_cache[3],
+                        ],
+                        frame_type: header_cache[4],
+                        len: (header_cache[5] as u16) << 8 | (header_cache[6] as u16),
+                        frame_num: header_cache[7],
+                    };
+                    log::debug!("{:?} {}", header.version, header.frame_num);
+                    // 空帧没有数据，直接读下一个帧头
+                    if header.len == 0 {
+                        header_cache.clear();
+                    } else {
+                        state = BluetrumVoiceDumpState::Body;
+                    }
+                }
+            }
+            BluetrumVoiceDumpState::Body => {
+                match header.frame_type {
+                    0 => outs[0].write_all(&[data])?,
+                    2 => outs[1].write_all(&[data])?,
+                    4 => outs[2].write_all(&[data])?,
+                    _ => {}
+                };
+
+                cnt += 1;
+                if cnt == header.len {
+                    state = BluetrumVoiceDumpState::Header;
+                    header_cache.clear();
+                    cnt = 0;
+                }
+            }
+        }
+        Ok(())
+    })
+}
+
+/// MOSI 数据转成十六进制文本
+pub fn spi_txt<R: BufRead, W: Write>(reader: R, out: &mut W) -> Result<()> {
+    write_hex_txt(reader, KINGST_SPI_FILE_FORMAT, out)
+}
+
+/// UART 数据转成十六进制文本
+pub fn uart<R: BufRead, W: Write>(_args: &LogicUARTArgs, reader: R, out: &mut W) -> Result<()> {
+    write_hex_txt(reader, KINGST_UART_FILE_FORMAT, out)
+}
+
+/// 读写缓冲区大小
+const BUF_SIZE: usize = 128 * 1024;
+
+fn open(path: &Path, progress: &Progress) -> Result<BufReader<ProgressReader<fs::Reader>>> {
+    Ok(BufReader::with_capacity(BUF_SIZE, progress.open(path)?))
+}
+
+/// 转换 SPI 导出文件，按 `output` 写出，返回输出文件路径
+///
+/// 输出格式 RAW 为 `out`，蓝讯 DUMP 为 `out1`/`out2`/`out3`，TXT 为 `txt`
+pub fn spi_file(
+    args: &LogicSpiArgs,
+    path: &Path,
+    output: &OutputPolicy,
+    progress: &Progress,
+) -> Result<Vec<PathBuf>> {
+    match args.conv_type {
+        SpiConvType::RAW => {
+            let out_path = output.resolve(path, "out")?;
+            let reader = open(path, progress)?;
+            fs::write_atomic(&out_path, |out| spi_raw(reader, out))?;
+            Ok(vec![out_path])
+        }
+        SpiConvType::BluetrumVoiceDump => {
+            output.check_formats(&["out1", "out2", "out3"])?;
+            let out_paths = [
+                output.resolve(path, "out1")?,
+                output.resolve(path, "out2")?,
+                output.resolve(path, "out3")?,
+            ];
+            let reader = open(path, progress)?;
+            let [out1, out2, out3] = &out_paths;
+            fs::write_atomic_all([out1, out2, out3], |outs| spi_bluetrum(reader, outs))?;
+            Ok(out_paths.to_vec())
+        }
+        SpiConvType::TXT => {
+            let out_path = output.resolve(path, "txt")?;
+            let reader = open(path, progress)?;
+            fs::write_atomic(&out_path, |out| spi_txt(reader, out))?;
+            Ok(vec![out_path])
+        }
+    }
+}
+
+/// 转换 IIS 导出文件，输出格式为 `out`
+pub fn iis_file(
+    args: &LogicIISArgs,
+    path: &Path,
+    output: &OutputPolicy,
+    progress: &Progress,
+) -> Result<Vec<PathBuf>> {
+    let out_path = output.resolve(path, "out")?;
+    let reader = open(path, progress)?;
+    fs::write_atomic(&out_path, |out| iis(args, reader, out))?;
+    Ok(vec![out_path])
+}
+
+/// 转换 UART 导出文件，输出格式为 `txt`
+pub fn uart_file(
+    args: &LogicUARTArgs,
+    path: &Path,
+    output: &OutputPolicy,
+    progress: &Progress,
+) -> Result<Vec<PathBuf>> {
+    let out_path = output.resolve(path, "txt")?;
+    let reader = open(path, progress)?;
+    fs::write_atomic(&out_path, |out| uart(args, reader, out))?;
+    Ok(vec![out_path])
+}
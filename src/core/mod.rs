@@ -0,0 +1,24 @@
+//! 与界面无关的处理核心，页面和命令行都调用这里的函数
+//!
+//! 每个转换都有基于 `Read`/`Write` 的纯函数，另外提供按文件路径处理的便捷函数，
+//! 出错时返回 [`Error`] 而不是 panic。
+
+#[cfg(feature = "analyze")]
+pub mod analyze;
+#[cfg(feature = "convert")]
+pub mod convert;
+pub mod encoding;
+mod error;
+pub mod fs;
+#[cfg(feature = "hardfault")]
+pub mod hardfault;
+#[cfg(feature = "hci")]
+pub mod hci;
+#[cfg(feature = "logic")]
+pub mod logic;
+pub mod output;
+mod progress;
+
+pub use error::{Error, Result};
+pub use output::{Conflict, OutputPolicy};
+pub use progress::{Progress, ProgressReader};
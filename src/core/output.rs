@@ -0,0 +1,143 @@
+//! 输出文件的位置、命名和已存在时的处理
+
+use std::path::{Component, Path, PathBuf};
+
+use super::{fs, Error, Result};
+
+/// 默认模板，和以前一样在输入文件名后面加上格式后缀
+pub const DEFAULT_TEMPLATE: &str = "{name}.{format}";
+
+/// 输出文件已经存在时的处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub enum Conflict {
+    #[default]
+    Overwrite,
+    /// 不处理这个输入文件，返回 [`Error::Skipped`]
+    Skip,
+    /// 在文件名后加 `_1`、`_2` ... 直到不冲突
+    Suffix,
+}
+
+/// 所有工具共用的输出策略
+///
+/// 模板里可以用的变量：
+/// - `{name}` 输入文件名，`capture.csv`
+/// - `{stem}` 去掉扩展名的输入文件名，`capture`
+/// - `{format}` 输出格式，比如 `out`、`out1`、`txt`
+/// - `{timestamp}` 开始处理的本地时间，`20240102-150405`
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct OutputPolicy {
+    /// 输出目录，`None` 时写在输入文件旁边
+    pub dir: Option<PathBuf>,
+    pub template: String,
+    pub conflict: Conflict,
+}
+
+impl Default for OutputPolicy {
+    fn default() -> Self {
+        OutputPolicy {
+            dir: None,
+            template: DEFAULT_TEMPLATE.to_string(),
+            conflict: Conflict::default(),
+        }
+    }
+}
+
+impl OutputPolicy {
+    fn template(&self) -> &str {
+        if self.template.is_empty() {
+            DEFAULT_TEMPLATE
+        } else {
+            &self.template
+        }
+    }
+
+    /// 一次处理写出 `formats` 这几种输出时，模板里没有 `{format}` 的话它们会用同一个文件名
+    pub fn check_formats(&self, formats: &[&str]) -> Result<()> {
+        if formats.len() > 1 && !self.template().contains("{format}") {
+            return Err(Error::SameOutputName(self.template().to_string()));
+        }
+        Ok(())
+    }
+
+    /// 只渲染文件名，不检查是否已经存在
+    pub fn file_name(&self, input: &Path, format: &str) -> String {
+        let name = input
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        let stem = input
+            .file_stem()
+            .map(|stem| stem.to_string_lossy())
+            .unwrap_or_default();
+        let mut file_name = self
+            .template()
+            .replace("{name}", &name)
+            .replace("{stem}", &stem)
+            .replace("{format}", format);
+        if file_name.contains("{timestamp}") {
+            let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+            file_name = file_name.replace("{timestamp}", &timestamp);
+        }
+        file_name
+    }
+
+    /// 计算 `input` 的 `format` 输出路径，需要时创建输出目录
+    pub fn resolve(&self, input: &Path, format: &str) -> Result<PathBuf> {
+        if let Some(dir) = &self.dir {
+            fs::create_dir_all(dir)?;
+        }
+        self.plan(input, format)
+    }
+
+    /// 和 [`OutputPolicy::resolve`] 一样，但不创建输出目录，预览时使用
+    pub fn plan(&self, input: &Path, format: &str) -> Result<PathBuf> {
+        let dir = match &self.dir {
+            Some(dir) => dir.clone(),
+            None => input.parent().map(Path::to_path_buf).unwrap_or_default(),
+        };
+        let file_name = self.file_name(input, format);
+        if !is_file_name(&file_name) {
+            return Err(Error::InvalidOutputName(file_name));
+        }
+        let path = dir.join(file_name);
+        if fs::same_file(&path, input) {
+            return Err(Error::OutputIsInput(path));
+        }
+        if !fs::exists(&path) {
+            return Ok(path);
+        }
+
+        match self.conflict {
+            Conflict::Overwrite => Ok(path),
+            Conflict::Skip => Err(Error::Skipped(path)),
+            Conflict::Suffix => Ok(next_free(&path)),
+        }
+    }
+}
+
+/// 只能是输出目录里的一个文件名，不能带目录或者 `..`
+fn is_file_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(first)), None) if first == name
+    ) && !name.contains(['/', '\\'])
+}
+
+/// `a.txt` -> `a_1.txt`、`a_2.txt` ...
+fn next_free(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let ext = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| path.with_file_name(format!("{}_{}{}", stem, n, ext)))
+        .find(|candidate| !fs::exists(candidate))
+        .unwrap()
+}
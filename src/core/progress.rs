@@ -0,0 +1,105 @@
+//! 处理进度和取消
+
+use std::fmt;
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use super::fs;
+
+/// 可以跨线程共享的进度，按读取的字节数计数
+///
+/// 通过 [`Progress::open`] / [`Progress::reader`] 读取的数据会计入进度，
+/// 调用 [`Progress::cancel`] 之后读取会返回 [`super::Error::Cancelled`]。
+#[derive(Clone, Default, Debug)]
+pub struct Progress {
+    inner: Arc<ProgressInner>,
+}
+
+#[derive(Default, Debug)]
+struct ProgressInner {
+    processed: AtomicU64,
+    total: AtomicU64,
+    /// 总量已经由 [`Progress::set_total`] 给出，打开文件时不再累加
+    fixed_total: AtomicBool,
+    cancelled: AtomicBool,
+}
+
+impl Progress {
+    pub fn processed(&self) -> u64 {
+        self.inner.processed.load(Ordering::Relaxed)
+    }
+
+    /// 总字节数，未知时为 0
+    pub fn total(&self) -> u64 {
+        self.inner.total.load(Ordering::Relaxed)
+    }
+
+    pub fn add_total(&self, n: u64) {
+        self.inner.total.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// 预先给出总量，比如批量处理开始前把所有输入的大小加起来，进度就不会往回跳
+    pub fn set_total(&self, n: u64) {
+        self.inner.total.store(n, Ordering::Relaxed);
+        self.inner.fixed_total.store(true, Ordering::Relaxed);
+    }
+
+    pub fn add_processed(&self, n: u64) {
+        self.inner.processed.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn reader<R: Read>(&self, inner: R) -> ProgressReader<R> {
+        ProgressReader {
+            inner,
+            progress: self.clone(),
+        }
+    }
+
+    /// 打开文件并把文件大小计入总量
+    pub fn open(&self, path: &Path) -> io::Result<ProgressReader<fs::Reader>> {
+        let (file, len) = fs::open(path)?;
+        if !self.inner.fixed_total.load(Ordering::Relaxed) {
+            self.add_total(len);
+        }
+        Ok(self.reader(file))
+    }
+}
+
+/// 读取时累加进度并检查是否被取消
+pub struct ProgressReader<R> {
+    inner: R,
+    progress: Progress,
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.progress.is_cancelled() {
+            return Err(io::Error::new(io::ErrorKind::Other, Cancelled));
+        }
+        let n = self.inner.read(buf)?;
+        self.progress.add_processed(n as u64);
+        Ok(n)
+    }
+}
+
+/// 取消时 `ProgressReader` 返回的 io 错误内容
+#[derive(Debug)]
+pub(crate) struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
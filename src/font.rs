@@ -0,0 +1,284 @@
+//! 中文字体，egui 自带的字体没有 CJK 字形，中文会显示成方框
+//!
+//! 按顺序使用：设置里选择的字体文件、系统里找到的 CJK 字体、
+//! `bundled-font` feature 打包的子集字体（网页版只能用这个）。
+
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::component::{path_edit, Browse};
+use crate::tr;
+
+pub static FONT_KEY: &str = "Font";
+
+const FONT_NAME: &str = "cjk";
+
+/// 打包的子集字体，生成方法见 `assets/fonts/README.md`
+#[cfg(feature = "bundled-font")]
+static BUNDLED_FONT: &[u8] = include_bytes!("../assets/fonts/NotoSansSC-subset.otf");
+
+/// 当前使用的字体
+#[derive(Clone, Debug, Default)]
+enum FontSource {
+    /// 设置里选择的字体文件
+    Custom(PathBuf),
+    /// 系统里找到的字体
+    #[cfg(not(target_arch = "wasm32"))]
+    System(PathBuf),
+    #[cfg_attr(not(feature = "bundled-font"), allow(dead_code))]
+    Bundled,
+    /// 没有找到中文字体
+    #[default]
+    None,
+}
+
+/// 设置里的字体文件和当前使用的字体
+#[derive(Default)]
+pub struct FontSettings {
+    /// 为空时自动查找，会保存
+    pub path: String,
+    source: FontSource,
+    error: Option<String>,
+}
+
+impl FontSettings {
+    /// 选择的字体不能用时退回自动查找，错误留在设置里提示
+    pub fn new(ctx: &egui::Context, path: String) -> Self {
+        let mut settings = FontSettings {
+            path,
+            ..Default::default()
+        };
+        settings.apply(ctx);
+        if settings.error.is_some() {
+            if let Ok(source) = install(ctx, "") {
+                settings.source = source;
+            }
+        }
+        settings
+    }
+
+    fn apply(&mut self, ctx: &egui::Context) {
+        match install(ctx, &self.path) {
+            Ok(source) => {
+                self.source = source;
+                self.error = None;
+            }
+            Err(e) => {
+                log::warn!("font {}: {}", self.path, e);
+                self.error = Some(e.to_string());
+            }
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(tr!("font.label"));
+            path_edit(
+                ui,
+                &mut self.path,
+                tr!("font.hint"),
+                Browse::Open(&["ttf", "ttc", "otf", "otc"]),
+            );
+            if ui.button(tr!("font.apply")).clicked() {
+                self.apply(ui.ctx());
+            }
+        });
+        let current = match &self.source {
+            FontSource::Custom(path) => path.display().to_string(),
+            #[cfg(not(target_arch = "wasm32"))]
+            FontSource::System(path) => path.display().to_string(),
+            FontSource::Bundled => tr!("font.bundled").to_string(),
+            FontSource::None => tr!("font.none").to_string(),
+        };
+        ui.label(tr!("font.current", font = current));
+        if let Some(e) = &self.error {
+            ui.colored_label(ui.visuals().error_fg_color, e);
+        }
+    }
+}
+
+/// 安装字体，`custom` 不为空时只用这个文件，读不到或者不是字体文件时返回错误，
+/// 原来的字体保持不变
+fn install(ctx: &egui::Context, custom: &str) -> io::Result<FontSource> {
+    let (source, data) = if custom.is_empty() {
+        match find() {
+            Some(found) => found,
+            None => return Ok(FontSource::None),
+        }
+    } else {
+        let path = PathBuf::from(custom);
+        let data = std::fs::read(&path)?;
+        (FontSource::Custom(path), data)
+    };
+
+    check(&data)?;
+
+    let mut fonts = egui::FontDefinitions::default();
+    fonts.font_data.insert(
+        FONT_NAME.to_owned(),
+        Arc::new(egui::FontData::from_owned(data)),
+    );
+
+    // 中文字体优先用于普通文字
+    fonts
+        .families
+        .entry(egui::FontFamily::Proportional)
+        .or_default()
+        .insert(0, FONT_NAME.to_owned());
+
+    // 等宽字体只在缺字时使用
+    fonts
+        .families
+        .entry(egui::FontFamily::Monospace)
+        .or_default()
+        .push(FONT_NAME.to_owned());
+
+    ctx.set_fonts(fonts);
+    Ok(source)
+}
+
+/// egui 遇到无效的字体会 panic，使用前先检查一遍
+fn check(data: &[u8]) -> io::Result<()> {
+    ab_glyph::FontRef::try_from_slice(data)
+        .map(|_| ())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// 依次尝试系统字体和打包字体，跳过读不到或者解析不了的文件（比如 `.pcf` 点阵字体）
+fn find() -> Option<(FontSource, Vec<u8>)> {
+    #[cfg(not(target_arch = "wasm32"))]
+    for path in system_fonts() {
+        let data = match std::fs::read(&path) {
+            Ok(data) => data,
+            Err(e) => {
+                log::debug!("font {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        match check(&data) {
+            Ok(()) => {
+                log::info!("use font {}", path.display());
+                return Some((FontSource::System(path), data));
+            }
+            Err(e) => log::debug!("font {}: {}", path.display(), e),
+        }
+    }
+
+    #[cfg(feature = "bundled-font")]
+    return Some((FontSource::Bundled, BUNDLED_FONT.to_vec()));
+
+    #[cfg(not(feature = "bundled-font"))]
+    None
+}
+
+/// 可能存在的系统字体，靠前的优先
+#[cfg(target_os = "windows")]
+fn system_fonts() -> Vec<PathBuf> {
+    let dir = std::env::var_os("WINDIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(r"C:\Windows"))
+        .join("Fonts");
+    ["msyh.ttc", "msyh.ttf", "simhei.ttf", "simsun.ttc"]
+        .iter()
+        .map(|name| dir.join(name))
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn system_fonts() -> Vec<PathBuf> {
+    [
+        "/System/Library/Fonts/PingFang.ttc",
+        "/System/Library/Fonts/Hiragino Sans GB.ttc",
+        "/System/Library/Fonts/STHeiti Medium.ttc",
+        "/Library/Fonts/Arial Unicode.ttf",
+    ]
+    .iter()
+    .map(PathBuf::from)
+    .collect()
+}
+
+/// 先问 fontconfig 支持中文的字体，没有 `fc-list` 时在 fontconfig 的默认目录里按文件名找
+#[cfg(all(unix, not(target_os = "macos")))]
+fn system_fonts() -> Vec<PathBuf> {
+    if let Ok(output) = std::process::Command::new("fc-list")
+        .args([":lang=zh", "--format=%{file}\n"])
+        .output()
+    {
+        if output.status.success() {
+            let mut listed: Vec<PathBuf> = String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(PathBuf::from)
+                .collect();
+            listed.sort_by_key(|path| cjk_font_rank(&path.to_string_lossy()));
+            return listed;
+        }
+    }
+
+    let mut dirs = vec![
+        PathBuf::from("/usr/share/fonts"),
+        PathBuf::from("/usr/local/share/fonts"),
+    ];
+    if let Some(home) = std::env::var_os("HOME").map(PathBuf::from) {
+        dirs.push(home.join(".local/share/fonts"));
+        dirs.push(home.join(".fonts"));
+    }
+    let mut found = Vec::new();
+    for dir in dirs {
+        collect_fonts(&dir, &mut found);
+    }
+    // 按名字列表的顺序排优先级
+    found.sort_by_key(|path| cjk_font_rank(&path.to_string_lossy()));
+    found
+}
+
+#[cfg(not(any(unix, target_os = "windows", target_arch = "wasm32")))]
+fn system_fonts() -> Vec<PathBuf> {
+    Vec::new()
+}
+
+/// 常见的开源中文字体文件名，靠前的优先
+#[cfg(all(unix, not(target_os = "macos")))]
+const CJK_FONT_NAMES: &[&str] = &[
+    "NotoSansCJK",
+    "NotoSansSC",
+    "SourceHanSans",
+    "wqy-microhei",
+    "wqy-zenhei",
+    "DroidSansFallback",
+    "NotoSerifCJK",
+    "uming",
+    "ukai",
+];
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn cjk_font_rank(path: &str) -> usize {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    CJK_FONT_NAMES
+        .iter()
+        .position(|cjk| name.starts_with(cjk))
+        .unwrap_or(CJK_FONT_NAMES.len())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn is_cjk_font_name(path: &str) -> bool {
+    cjk_font_rank(path) < CJK_FONT_NAMES.len()
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn collect_fonts(dir: &std::path::Path, found: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_fonts(&path, found);
+        } else if is_cjk_font_name(&path.to_string_lossy())
+            && crate::component::has_extension(&path, &["ttf", "ttc", "otf", "otc"])
+        {
+            found.push(path);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! 所有工具的运行历史，保存在 eframe storage 里
+
+use std::path::PathBuf;
+
+use crate::component::{FinishedRun, Settings, ToolInfo};
+use crate::tr;
+
+pub static HISTORY_KEY: &str = "History";
+
+/// 最多保留的记录数，超出时丢掉最旧的
+const MAX_RUNS: usize = 200;
+
+/// 一次批量处理的记录
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct RunRecord {
+    /// 工具的注册表 id
+    pub tool: String,
+    /// 结束时的本地时间
+    pub time: String,
+    pub inputs: Vec<PathBuf>,
+    /// 展开成 `inputs` 之前列表里的文件和文件夹，重新运行时用它们
+    #[serde(default)]
+    pub roots: Vec<PathBuf>,
+    /// 页面序列化的处理选项，见 [`FinishedRun::options`]
+    pub options: String,
+    pub outputs: Vec<PathBuf>,
+    pub duration_ms: u64,
+    pub success: bool,
+}
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct History {
+    version: u32,
+    /// 最新的在前面
+    runs: Vec<RunRecord>,
+}
+
+impl Settings for History {
+    const VERSION: u32 = 1;
+
+    fn version(&mut self) -> &mut u32 {
+        &mut self.version
+    }
+}
+
+impl History {
+    pub fn push(&mut self, tool: &str, run: FinishedRun) {
+        self.runs.insert(
+            0,
+            RunRecord {
+                tool: tool.to_string(),
+                time: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                inputs: run.inputs,
+                roots: run.roots,
+                options: run.options,
+                outputs: run.outputs,
+                duration_ms: run.duration.as_millis() as u64,
+                success: run.success,
+            },
+        );
+        self.runs.truncate(MAX_RUNS);
+    }
+
+    /// 显示历史列表，返回点了“重新运行”的记录
+    pub fn ui(&mut self, ui: &mut egui::Ui, tools: &[ToolInfo]) -> Option<RunRecord> {
+        let mut rerun = None;
+
+        ui.heading(tr!("history.heading"));
+        ui.horizontal(|ui| {
+            ui.label(tr!("history.count", count = self.runs.len()));
+            if ui
+                .add_enabled(
+                    !self.runs.is_empty(),
+                    egui::Button::new(tr!("common.clear")),
+                )
+                .clicked()
+            {
+                self.runs.clear();
+            }
+        });
+        ui.separator();
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("history")
+                .num_columns(6)
+                .spacing([20.0, 4.0])
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong(tr!("history.time"));
+                    ui.strong(tr!("history.tool"));
+                    ui.strong(tr!("history.inputs"));
+                    ui.strong(tr!("history.duration"));
+                    ui.strong(tr!("history.result"));
+                    ui.label("");
+                    ui.end_row();
+
+                    for run in &self.runs {
+                        let tool = tools.iter().find(|tool| tool.id == run.tool);
+                        ui.label(&run.time);
+                        ui.label(tool.map_or(run.tool.as_str(), |tool| tr!(tool.name)));
+
+                        let inputs = join_paths(&run.inputs);
+                        match run.inputs.as_slice() {
+                            [input] => ui.label(input.display().to_string()),
+                            inputs_list => {
+                                ui.label(tr!("history.files", count = inputs_list.len()))
+                            }
+                        }
+                        .on_hover_text(format!("{}\n\n{}", inputs, run.options));
+
+                        ui.label(format!("{:.2}s", run.duration_ms as f32 / 1000.0));
+                        if run.success {
+                            ui.colored_label(egui::Color32::GREEN, "✔")
+                                .on_hover_text(join_paths(&run.outputs));
+                        } else {
+                            ui.colored_label(ui.visuals().error_fg_color, "✖");
+                        }
+
+                        // 工具没有编译进来时不能重新运行
+                        if ui
+                            .add_enabled(tool.is_some(), egui::Button::new(tr!("history.rerun")))
+                            .clicked()
+                        {
+                            rerun = Some(run.clone());
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+
+        rerun
+    }
+}
+
+fn join_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,220 @@
+//! English catalog
+
+pub static CATALOG: &[(&str, &str)] = &[
+    // home
+    ("home.title", "Home"),
+    ("home.tab", "🏠 Home"),
+    ("home.language", "Language"),
+    ("home.compile_time", "Built: {time}"),
+    ("home.git", "git: {hash} ({time})"),
+    (
+        "home.help.tabs",
+        "Pick a tool below; drag tabs to show several tools side by side",
+    ),
+    ("home.help.drop", "Files can be dropped straight onto a tool's window\n"),
+    ("home.help.encoding", "About the input file encoding:"),
+    (
+        "home.help.encoding_other",
+        "utf8 is used as is, other guesses the encoding; a BOM at the start of the file always wins",
+    ),
+    (
+        "home.help.encoding_choose",
+        "Prefer choosing the encoding yourself, the guess can be wrong; when unsure, click 🔍 next to the encoding to compare previews",
+    ),
+    (
+        "home.help.hci",
+        "The hci tool turns CMD/EVT/ACL records into a btsnoop file that opens in Wireshark, everything else (including Chinese text) is ignored",
+    ),
+    (
+        "home.help.untouched",
+        "Input files are never modified, tick \"Save a UTF-8 copy\" to get a decoded copy",
+    ),
+    ("home.workspace", "Workspace file"),
+    ("home.run_all", "Run all"),
+    ("workspace.saved", "Saved {count} tools"),
+    ("workspace.opened", "Opened"),
+    ("workspace.unsupported", "Opened, unsupported tools: {tools}"),
+    ("workspace.failed", "Some tools could not be restored: {tools}"),
+    // history
+    ("history.title", "🕘 History"),
+    ("history.heading", "History"),
+    ("history.count", "{count} runs"),
+    ("history.time", "Time"),
+    ("history.tool", "Tool"),
+    ("history.inputs", "Inputs"),
+    ("history.duration", "Duration"),
+    ("history.result", "Result"),
+    ("history.files", "{count} files"),
+    ("history.rerun", "Run again"),
+    // log console
+    ("console.title", "📜 Log"),
+    ("console.heading", "Log"),
+    ("console.level", "Level"),
+    ("console.job", "Job"),
+    ("console.all_jobs", "All jobs"),
+    ("console.copy", "Copy"),
+    // common buttons
+    ("common.save", "Save"),
+    ("common.open", "Open"),
+    ("common.clear", "Clear"),
+    ("common.close", "Close"),
+    ("common.process", "Process"),
+    // background jobs
+    ("job.cancel", "Cancel"),
+    ("job.succeeded", "✔ {name} finished ({elapsed})"),
+    ("job.failed", "✖ {name} failed ({elapsed}): {error}"),
+    ("job.cancelled", "{name} cancelled ({elapsed})"),
+    ("job.panicked", "worker crashed: {message}"),
+    ("job.batch", "{name} ({count} files)"),
+    // dialogs
+    ("dialog.browse", "Browse…"),
+    ("dialog.all_files", "All files"),
+    ("dialog.add_folder", "Add folder…"),
+    // file list
+    ("queue.files", "Files"),
+    ("queue.files_and_folders", "Files and folders"),
+    ("queue.add", "Add"),
+    ("queue.recent", "Recent"),
+    ("queue.skipped", "Skipped"),
+    ("queue.exists", "{path} already exists"),
+    ("queue.dropping", "Dropping files:\n\n{files}"),
+    // watch folder
+    ("watch.folder", "Watch folder"),
+    ("watch.hint", "New files are processed automatically"),
+    ("watch.start", "Start"),
+    ("watch.stop", "Stop"),
+    // output
+    ("output.dir", "Output folder"),
+    ("output.dir_hint", "Empty: next to the input file"),
+    ("output.name", "Output file name"),
+    (
+        "output.name_help",
+        "{name} input file name\n{stem} input file name without extension\n{format} output format\n{timestamp} processing time",
+    ),
+    ("output.conflict", "If the file exists"),
+    ("output.overwrite", "Overwrite"),
+    ("output.skip", "Skip"),
+    ("output.suffix", "Add number"),
+    // font
+    ("font.label", "Font file"),
+    ("font.hint", "Empty: find a CJK system font"),
+    ("font.apply", "Apply"),
+    ("font.current", "Current font: {font}"),
+    ("font.bundled", "bundled font"),
+    ("font.none", "no CJK font found"),
+    // encoding
+    ("encoding.select", "Input file encoding"),
+    ("encoding.label", "Encoding"),
+    ("encoding.save_normalized", "Save a UTF-8 copy"),
+    (
+        "encoding.save_normalized_help",
+        "Also write the decoded input as the utf8 output; the input file is never modified",
+    ),
+    ("encoding.inspect", "Inspect: preview the file in each likely encoding"),
+    ("encoding.inspector_title", "Encoding inspector"),
+    ("encoding.inspect_file", "File"),
+    ("encoding.reinspect", "Inspect again"),
+    ("encoding.confidence", "confidence {value}%"),
+    ("encoding.invalid_count", "{count} undecodable"),
+    ("encoding.use", "Use"),
+    ("encoding.no_candidates", "No usable encoding found"),
+    (
+        "encoding.invalid_bytes_help",
+        "Only the start of the file is read; red \\xAB are bytes this encoding cannot decode",
+    ),
+    // tools
+    ("logic.name", "Logic Tool"),
+    (
+        "logic.description",
+        "Convert SPI/IIS/UART csv exported by a logic analyzer",
+    ),
+    ("logic.protocol", "Protocol"),
+    ("logic.job", "Logic {protocol}"),
+    ("logic.spi_format", "SPI format"),
+    ("logic.bluetrum_dump", "Bluetrum audio DUMP"),
+    ("hardfault.name", "Hardfault Tool"),
+    (
+        "hardfault.description",
+        "Decode registers printed on hardfault / watchdog reset",
+    ),
+    ("hardfault.select_group", "Register group to show"),
+    ("hardfault.group", "Register group"),
+    ("hci.name", "HCI Tool"),
+    (
+        "hci.not_utf8",
+        "Some files are not valid UTF-8 and were not converted, select their encoding",
+    ),
+    ("hci.use_other", "Detect automatically"),
+    (
+        "hci.description",
+        "Convert hci text logs to btsnoop files",
+    ),
+    ("analyze.name", "Analyze Tool"),
+    (
+        "analyze.description",
+        "Filter logic analyzer logs by type",
+    ),
+    ("analyze.types", "Types to convert"),
+    ("convert.name", "Convert Tool"),
+    (
+        "convert.description",
+        "Convert the encoding and line endings of text files in bulk",
+    ),
+    ("convert.job_preview", "Convert preview"),
+    ("convert.job", "Convert"),
+    ("convert.patterns", "File names"),
+    (
+        "convert.patterns_help",
+        "Files to convert inside folders: wildcards separated by spaces, * and ? are supported; empty converts every file",
+    ),
+    ("convert.recursive", "Include subfolders"),
+    ("convert.target", "Output encoding"),
+    ("convert.line_ending", "Line endings"),
+    ("convert.keep", "Keep"),
+    ("convert.preview", "Preview"),
+    ("convert.convert", "Convert"),
+    ("convert.preview_first", "Preview first and check the report"),
+    ("convert.no_files", "No files to convert"),
+    (
+        "convert.preview_summary",
+        "Preview: {count} files, {problems} with problems, nothing written yet",
+    ),
+    (
+        "convert.done_summary",
+        "Converted {count} files, {problems} with problems",
+    ),
+    ("convert.stale", "The options changed, preview again"),
+    ("convert.file", "File"),
+    ("convert.source", "Source encoding"),
+    ("convert.auto", "auto"),
+    ("convert.line_endings", "Line endings"),
+    ("convert.decode_errors", "Undecodable"),
+    ("convert.unmappable", "Unencodable"),
+    ("convert.output", "Output"),
+    ("convert.offsets", "byte offsets: {offsets}"),
+    // errors
+    (
+        "error.invalid_header",
+        "unexpected file header, expected \"{expected}\"; check the protocol and export format",
+    ),
+    ("error.missing_column", "missing '{column}' column"),
+    ("error.invalid_value", "line {line}: invalid value in \"{content}\""),
+    (
+        "error.not_utf8",
+        "file is not valid utf8, select the input encoding",
+    ),
+    ("error.cancelled", "cancelled"),
+    ("error.skipped", "skipped, {path} already exists"),
+    (
+        "error.output_is_input",
+        "output {path} would overwrite the input, change the output folder or name",
+    ),
+    (
+        "error.invalid_output_name",
+        "output name \"{name}\" must be a file name without folders",
+    ),
+    (
+        "error.same_output_name",
+        "output name \"{template}\" gives every output the same name, add {format}",
+    ),
+];
@@ -0,0 +1,110 @@
+//! 界面文字的翻译
+//!
+//! 每种语言一份词条表（`zh.rs`、`en.rs`），key 是点分的名字，值里的 `{name}`
+//! 占位符由 [`tr!`] 替换。英文词条缺失时用中文，都没有时直接显示 key。
+
+mod en;
+mod zh;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use once_cell::sync::Lazy;
+
+pub static LANGUAGE_KEY: &str = "Language";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum Language {
+    #[default]
+    Chinese,
+    English,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::Chinese, Language::English];
+
+    /// 语言自己的名字，选择框里不翻译
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::Chinese => "中文",
+            Language::English => "English",
+        }
+    }
+
+    fn catalog(self) -> &'static HashMap<&'static str, &'static str> {
+        static ZH: Lazy<HashMap<&str, &str>> = Lazy::new(|| zh::CATALOG.iter().copied().collect());
+        static EN: Lazy<HashMap<&str, &str>> = Lazy::new(|| en::CATALOG.iter().copied().collect());
+        match self {
+            Language::Chinese => &ZH,
+            Language::English => &EN,
+        }
+    }
+}
+
+static CURRENT: AtomicU8 = AtomicU8::new(Language::Chinese as u8);
+
+pub fn language() -> Language {
+    match CURRENT.load(Ordering::Relaxed) {
+        1 => Language::English,
+        _ => Language::Chinese,
+    }
+}
+
+pub fn set_language(language: Language) {
+    CURRENT.store(language as u8, Ordering::Relaxed);
+}
+
+/// 当前语言的词条
+pub fn tr(key: &'static str) -> &'static str {
+    language()
+        .catalog()
+        .get(key)
+        .or_else(|| Language::Chinese.catalog().get(key))
+        .copied()
+        .unwrap_or(key)
+}
+
+/// 翻译词条，带参数时替换对应的 `{name}` 占位符
+///
+/// ```ignore
+/// tr!("history.count", count = runs.len())
+/// ```
+#[macro_export]
+macro_rules! tr {
+    ($key:expr) => {
+        $crate::i18n::tr($key)
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut text = $crate::i18n::tr($key).to_string();
+        $(
+            text = text.replace(concat!("{", stringify!($name), "}"), &$value.to_string());
+        )+
+        text
+    }};
+}
+
+/// 处理错误的界面提示，日志和命令行仍然用 [`crate::core::Error`] 的英文 `Display`
+#[cfg(any_tool)]
+pub fn error_message(e: &crate::core::Error) -> String {
+    use crate::core::Error;
+
+    match e {
+        Error::Io(e) => e.to_string(),
+        Error::Csv(e) => e.to_string(),
+        Error::InvalidHeader { expected } => {
+            tr!("error.invalid_header", expected = expected)
+        }
+        Error::MissingColumn(column) => tr!("error.missing_column", column = column),
+        Error::InvalidValue { line, content } => {
+            tr!("error.invalid_value", line = line, content = content)
+        }
+        Error::NotUtf8 => tr!("error.not_utf8").to_string(),
+        Error::Cancelled => tr!("error.cancelled").to_string(),
+        Error::Skipped(path) => tr!("error.skipped", path = path.display()),
+        Error::OutputIsInput(path) => tr!("error.output_is_input", path = path.display()),
+        Error::InvalidOutputName(name) => tr!("error.invalid_output_name", name = name),
+        Error::SameOutputName(template) => {
+            tr!("error.same_output_name", template = template)
+        }
+    }
+}
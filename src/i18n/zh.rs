@@ -0,0 +1,208 @@
+//! 中文词条，也是其他语言缺词条时的后备
+
+pub static CATALOG: &[(&str, &str)] = &[
+    // 首页
+    ("home.title", "Home"),
+    ("home.tab", "🏠 首页"),
+    ("home.language", "语言"),
+    ("home.compile_time", "编译时间：{time}"),
+    ("home.git", "git 信息：{hash} ({time})"),
+    (
+        "home.help.tabs",
+        "在下方选择对应的功能，可以拖动标签页把多个工具并排显示",
+    ),
+    ("home.help.drop", "需要处理的文件可以直接拖入对应窗口\n"),
+    ("home.help.encoding", "输入文件编码的说明："),
+    (
+        "home.help.encoding_other",
+        "utf8 不会转化，other 会自己猜测编码；文件开头有 BOM 时总是按 BOM 解码",
+    ),
+    (
+        "home.help.encoding_choose",
+        "建议自己选择编码格式，猜测的编码可能会不对；不确定时点编码旁边的 🔍 对比各个编码的预览",
+    ),
+    (
+        "home.help.hci",
+        "hci tool 把 CMD/EVT/ACL 记录转换成 btsnoop 文件，可以用 Wireshark 打开，其他内容（包括中文）都会忽略",
+    ),
+    (
+        "home.help.untouched",
+        "输入文件不会被修改，需要 UTF-8 文件时勾选“另存 UTF-8 副本”",
+    ),
+    ("home.workspace", "工作区文件"),
+    ("home.run_all", "全部运行"),
+    ("workspace.saved", "已保存 {count} 个工具"),
+    ("workspace.opened", "已打开"),
+    ("workspace.unsupported", "已打开，不支持的工具：{tools}"),
+    ("workspace.failed", "部分工具没能恢复：{tools}"),
+    // 历史记录
+    ("history.title", "🕘 历史记录"),
+    ("history.heading", "历史记录"),
+    ("history.count", "共 {count} 条"),
+    ("history.time", "时间"),
+    ("history.tool", "工具"),
+    ("history.inputs", "输入"),
+    ("history.duration", "耗时"),
+    ("history.result", "结果"),
+    ("history.files", "{count} 个文件"),
+    ("history.rerun", "重新运行"),
+    // 日志
+    ("console.title", "📜 日志"),
+    ("console.heading", "日志"),
+    ("console.level", "级别"),
+    ("console.job", "任务"),
+    ("console.all_jobs", "全部任务"),
+    ("console.copy", "复制"),
+    // 通用按钮
+    ("common.save", "保存"),
+    ("common.open", "打开"),
+    ("common.clear", "清空"),
+    ("common.close", "关闭"),
+    ("common.process", "处理"),
+    // 后台任务
+    ("job.cancel", "取消"),
+    ("job.succeeded", "✔ {name} 完成 ({elapsed})"),
+    ("job.failed", "✖ {name} 失败 ({elapsed}): {error}"),
+    ("job.cancelled", "{name} 已取消 ({elapsed})"),
+    ("job.panicked", "处理线程崩溃：{message}"),
+    ("job.batch", "{name} ({count} 个文件)"),
+    // 对话框
+    ("dialog.browse", "浏览…"),
+    ("dialog.all_files", "所有文件"),
+    ("dialog.add_folder", "添加文件夹…"),
+    // 文件列表
+    ("queue.files", "文件地址"),
+    ("queue.files_and_folders", "文件和文件夹"),
+    ("queue.add", "添加"),
+    ("queue.recent", "最近"),
+    ("queue.skipped", "跳过"),
+    ("queue.exists", "{path} 已经存在"),
+    ("queue.dropping", "拖入文件：\n\n{files}"),
+    // 监控文件夹
+    ("watch.folder", "监控文件夹"),
+    ("watch.hint", "新文件会自动处理"),
+    ("watch.start", "开始"),
+    ("watch.stop", "停止"),
+    // 输出
+    ("output.dir", "输出目录"),
+    ("output.dir_hint", "留空时和输入文件放在一起"),
+    ("output.name", "输出文件名"),
+    (
+        "output.name_help",
+        "{name} 输入文件名\n{stem} 去掉扩展名的输入文件名\n{format} 输出格式\n{timestamp} 处理时间",
+    ),
+    ("output.conflict", "文件已存在时"),
+    ("output.overwrite", "覆盖"),
+    ("output.skip", "跳过"),
+    ("output.suffix", "加序号"),
+    // 字体
+    ("font.label", "字体文件"),
+    ("font.hint", "留空时自动查找系统中文字体"),
+    ("font.apply", "应用"),
+    ("font.current", "当前字体：{font}"),
+    ("font.bundled", "内置字体"),
+    ("font.none", "没有找到中文字体"),
+    // 编码
+    ("encoding.select", "选择输入文件编码"),
+    ("encoding.label", "文件编码"),
+    ("encoding.save_normalized", "另存 UTF-8 副本"),
+    (
+        "encoding.save_normalized_help",
+        "处理时按输出设置另存一份解码后的文件（格式 utf8），输入文件不会被修改",
+    ),
+    ("encoding.inspect", "编码检查：查看各个编码的解码预览"),
+    ("encoding.inspector_title", "编码检查"),
+    ("encoding.inspect_file", "文件"),
+    ("encoding.reinspect", "重新检查"),
+    ("encoding.confidence", "置信度 {value}%"),
+    ("encoding.invalid_count", "{count} 处无法解码"),
+    ("encoding.use", "使用"),
+    ("encoding.no_candidates", "没有找到可用的编码"),
+    (
+        "encoding.invalid_bytes_help",
+        "只读取文件开头，红色的 \\xAB 是按该编码无法解码的字节",
+    ),
+    // 工具
+    ("logic.name", "逻辑分析仪"),
+    (
+        "logic.description",
+        "逻辑分析仪导出的 SPI/IIS/UART csv 转换",
+    ),
+    ("logic.protocol", "协议类型"),
+    ("logic.job", "逻辑分析仪 {protocol}"),
+    ("logic.spi_format", "spi 格式"),
+    ("logic.bluetrum_dump", "蓝讯音频 DUMP 格式"),
+    ("hardfault.name", "Hardfault 解析"),
+    (
+        "hardfault.description",
+        "解析 hardfault / 看门狗复位打印的寄存器",
+    ),
+    ("hardfault.select_group", "选择需要显示的寄存器组"),
+    ("hardfault.group", "寄存器组"),
+    ("hci.name", "HCI 日志"),
+    ("hci.not_utf8", "有文件不是 utf8 编码，没有转换，请选择它们的编码"),
+    ("hci.use_other", "自动识别"),
+    ("hci.description", "hci 文本日志转换成 btsnoop 文件"),
+    ("analyze.name", "日志筛选"),
+    ("analyze.description", "按类型筛选逻辑分析仪抓到的日志"),
+    ("analyze.types", "需要转换的类型"),
+    ("convert.name", "编码转换"),
+    ("convert.description", "批量转换文本文件的编码和换行符"),
+    ("convert.job_preview", "转换预览"),
+    ("convert.job", "转换"),
+    ("convert.patterns", "文件名"),
+    (
+        "convert.patterns_help",
+        "文件夹里要转换的文件，用空格分开的通配符，支持 * 和 ?；留空时转换所有文件",
+    ),
+    ("convert.recursive", "包含子文件夹"),
+    ("convert.target", "输出编码"),
+    ("convert.line_ending", "换行符"),
+    ("convert.keep", "保持不变"),
+    ("convert.preview", "预览"),
+    ("convert.convert", "转换"),
+    ("convert.preview_first", "先预览，确认报告后再转换"),
+    ("convert.no_files", "没有找到要转换的文件"),
+    (
+        "convert.preview_summary",
+        "预览：{count} 个文件，{problems} 个有问题，还没有写入任何文件",
+    ),
+    (
+        "convert.done_summary",
+        "已转换 {count} 个文件，{problems} 个有问题",
+    ),
+    ("convert.stale", "选项已经修改，请重新预览"),
+    ("convert.file", "文件"),
+    ("convert.source", "输入编码"),
+    ("convert.auto", "自动"),
+    ("convert.line_endings", "换行符"),
+    ("convert.decode_errors", "无法解码"),
+    ("convert.unmappable", "无法编码"),
+    ("convert.output", "输出"),
+    ("convert.offsets", "字节偏移：{offsets}"),
+    // 错误
+    (
+        "error.invalid_header",
+        "文件表头不对，应该是 \"{expected}\"，检查协议类型和导出格式",
+    ),
+    ("error.missing_column", "缺少 '{column}' 列"),
+    (
+        "error.invalid_value",
+        "第 {line} 行：无法解析 \"{content}\"",
+    ),
+    ("error.not_utf8", "文件不是 utf8 编码，请选择输入文件编码"),
+    ("error.cancelled", "已取消"),
+    ("error.skipped", "跳过，{path} 已经存在"),
+    (
+        "error.output_is_input",
+        "输出 {path} 会覆盖输入文件，请修改输出目录或文件名",
+    ),
+    (
+        "error.invalid_output_name",
+        "输出文件名 \"{name}\" 不能带目录",
+    ),
+    (
+        "error.same_output_name",
+        "输出文件名 \"{template}\" 会让所有输出同名，请加上 {format}",
+    ),
+];
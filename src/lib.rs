@@ -1,5 +1,14 @@
 #![warn(clippy::all, rust_2018_idioms)]
+#![allow(clippy::upper_case_acronyms)] // SPI, IIS, UART, GBK ... read better in upper case
 
 mod app;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cli;
 mod component;
+pub mod console;
+pub mod core;
+mod font;
+mod history;
+mod i18n;
+pub mod workspace;
 pub use app::WorkToolApp;
@@ -4,7 +4,19 @@
 // When compiling natively:
 #[cfg(not(target_arch = "wasm32"))]
 fn main() -> eframe::Result<()> {
-    env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
+    // Any argument switches to the headless command line interface.
+    if std::env::args_os().len() > 1 {
+        #[cfg(windows)]
+        attach_console();
+        // Log warnings to stderr, set `RUST_LOG=debug` for more.
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+        std::process::exit(worktool::cli::run(std::env::args_os()));
+    }
+
+    // Log to the in-app console, and to stderr if you run with `RUST_LOG=debug`.
+    let stderr = env_logger::Builder::from_default_env().build();
+    let stderr_level = stderr.filter();
+    worktool::console::init(Some(Box::new(stderr)), stderr_level).ok();
 
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
@@ -24,20 +36,48 @@ fn main() -> eframe::Result<()> {
     )
 }
 
+/// Release builds use the windows subsystem and start without a console,
+/// so the command line interface writes to the console it was started from.
+#[cfg(windows)]
+fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // Fails when there is no parent console (started from Explorer) or when
+    // debug builds already have one, both are fine.
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
 // When compiling to web using trunk:
 #[cfg(target_arch = "wasm32")]
 fn main() {
-    // Redirect `log` message to `console.log` and friends:
-    eframe::WebLogger::init(log::LevelFilter::Debug).ok();
+    use eframe::wasm_bindgen::JsCast as _;
+
+    // Log to the in-app console and redirect `log` message to `console.log` and friends:
+    worktool::console::init(
+        Some(Box::new(eframe::WebLogger::new(log::LevelFilter::Debug))),
+        log::LevelFilter::Debug,
+    )
+    .ok();
 
     let web_options = eframe::WebOptions::default();
 
     wasm_bindgen_futures::spawn_local(async {
+        let canvas = web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.get_element_by_id("the_canvas_id"))
+            .and_then(|element| element.dyn_into::<web_sys::HtmlCanvasElement>().ok())
+            .expect("the_canvas_id is not a canvas");
+
         eframe::WebRunner::new()
             .start(
-                "the_canvas_id", // hardcode it
+                canvas,
                 web_options,
-                Box::new(|cc| Ok(Box::new(worktool::TemplateApp::new(cc)))),
+                Box::new(|cc| Ok(Box::new(worktool::WorkToolApp::new(cc)))),
             )
             .await
             .expect("failed to start eframe");
@@ -0,0 +1,97 @@
+//! 工作区文件，记录打开的工具、输入文件和所有选项，方便在另一台电脑上恢复
+//!
+//! 和工作区文件在同一个目录（或者子目录）下的输入保存为相对路径，
+//! 把工作区文件和数据一起打包发给别人就可以直接打开。
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const WORKSPACE_VERSION: u32 = 1;
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct Workspace {
+    pub version: u32,
+    pub tools: Vec<WorkspaceTool>,
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct WorkspaceTool {
+    /// 工具的注册表 id
+    pub tool: String,
+    pub inputs: Vec<PathBuf>,
+    /// 页面的处理选项，见 `Interface::options`
+    pub options: serde_json::Value,
+}
+
+impl Default for Workspace {
+    fn default() -> Self {
+        Workspace {
+            version: WORKSPACE_VERSION,
+            tools: Vec::new(),
+        }
+    }
+}
+
+impl Workspace {
+    pub fn push(&mut self, tool: &str, inputs: Vec<PathBuf>, options: &str) {
+        self.tools.push(WorkspaceTool {
+            tool: tool.to_string(),
+            inputs,
+            options: serde_json::from_str(options).unwrap_or_default(),
+        });
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let base = base_dir(path);
+        let workspace = Workspace {
+            version: self.version,
+            tools: self
+                .tools
+                .iter()
+                .map(|tool| WorkspaceTool {
+                    tool: tool.tool.clone(),
+                    inputs: tool
+                        .inputs
+                        .iter()
+                        .map(|input| input.strip_prefix(&base).unwrap_or(input).to_path_buf())
+                        .collect(),
+                    options: tool.options.clone(),
+                })
+                .collect(),
+        };
+        fs::write(path, serde_json::to_string_pretty(&workspace)?)
+    }
+
+    /// 读取工作区，相对路径按工作区文件所在的目录展开
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut workspace: Workspace = serde_json::from_slice(&fs::read(path)?)?;
+        if workspace.version > WORKSPACE_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "workspace version {} is newer than supported {}",
+                    workspace.version, WORKSPACE_VERSION
+                ),
+            ));
+        }
+        let base = base_dir(path);
+        for tool in &mut workspace.tools {
+            for input in &mut tool.inputs {
+                if input.is_relative() {
+                    *input = base.join(&input);
+                }
+            }
+        }
+        Ok(workspace)
+    }
+}
+
+/// 工作区文件所在的目录
+fn base_dir(path: &Path) -> PathBuf {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf())
+}
@@ -0,0 +1,57 @@
+#![cfg(feature = "analyze")]
+
+mod common;
+
+use std::fs;
+
+use common::{assert_golden, fixture, out_dir};
+use worktool::core::{analyze, Error, Progress};
+
+#[test]
+fn filter_types() {
+    let dir = out_dir("analyze");
+    let out = dir.join("spi_log.csv.out.txt");
+    let count = analyze::analyze_file(
+        "A,B",
+        &fixture("analyze/spi_log.csv"),
+        &out,
+        &Progress::default(),
+    )
+    .unwrap();
+    assert_eq!(count, 4);
+    assert_golden(&out, "analyze/spi_log.csv.out.txt");
+}
+
+#[test]
+fn missing_mosi() {
+    let dir = out_dir("analyze_missing_mosi");
+    let err = analyze::analyze_file(
+        "A",
+        &fixture("logic/iis.csv"),
+        &dir.join("iis.csv.out.txt"),
+        &Progress::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, Error::MissingColumn("MOSI")), "{:?}", err);
+    // 失败时不留下空的或者写了一半的输出
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+}
+
+#[test]
+fn output_is_input() {
+    let dir = out_dir("analyze_output_is_input");
+    let input = dir.join("spi_log.csv");
+    fs::copy(fixture("analyze/spi_log.csv"), &input).unwrap();
+    let err = analyze::analyze_file(
+        "A",
+        &input,
+        &dir.join(".").join("spi_log.csv"),
+        &Progress::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, Error::OutputIsInput(_)), "{:?}", err);
+    assert_eq!(
+        fs::read(&input).unwrap(),
+        fs::read(fixture("analyze/spi_log.csv")).unwrap()
+    );
+}
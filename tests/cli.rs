@@ -0,0 +1,136 @@
+#![cfg(all(
+    not(target_arch = "wasm32"),
+    any(
+        feature = "logic",
+        feature = "hardfault",
+        feature = "hci",
+        feature = "analyze",
+        feature = "convert"
+    )
+))]
+
+mod common;
+
+use std::process::Command;
+
+use common::fixture;
+
+fn worktool() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_worktool"))
+}
+
+#[cfg(feature = "logic")]
+#[test]
+fn logic_spi_txt() {
+    use common::{assert_golden, out_dir};
+
+    let dir = out_dir("cli_spi_txt");
+    let output = worktool()
+        .args(["logic", "spi", "--format", "txt", "--output-dir"])
+        .arg(&dir)
+        .arg(fixture("logic/spi.csv"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert_golden(&dir.join("spi.csv.txt"), "logic/spi.csv.txt");
+}
+
+#[cfg(feature = "hardfault")]
+#[test]
+fn hardfault_json() {
+    use common::assert_golden_bytes;
+
+    let output = worktool()
+        .args(["hardfault", "--json"])
+        .arg(fixture("hardfault/crash.log"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert_golden_bytes(&output.stdout, "hardfault/crash.log.json");
+}
+
+#[cfg(feature = "logic")]
+#[test]
+fn invalid_input_exit_code() {
+    use common::out_dir;
+
+    let dir = out_dir("cli_invalid_input");
+    let output = worktool()
+        .args(["logic", "spi", "--output-dir"])
+        .arg(&dir)
+        .arg(fixture("logic/iis.csv"))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+}
+
+#[cfg(feature = "analyze")]
+#[test]
+fn analyze_output_is_input() {
+    use common::out_dir;
+
+    let dir = out_dir("cli_analyze_output_is_input");
+    let input = dir.join("spi_log.csv");
+    std::fs::copy(fixture("analyze/spi_log.csv"), &input).unwrap();
+    let output = worktool()
+        .args(["analyze", "--types", "A", "-o"])
+        .arg(&input)
+        .arg(&input)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert_eq!(
+        std::fs::read(&input).unwrap(),
+        std::fs::read(fixture("analyze/spi_log.csv")).unwrap()
+    );
+}
+
+#[cfg(feature = "convert")]
+#[test]
+fn convert_folder() {
+    use common::{assert_golden, out_dir};
+
+    let dir = out_dir("cli_convert");
+    let input = dir.join("in");
+    std::fs::create_dir_all(input.join("sub")).unwrap();
+    std::fs::copy(fixture("hci/hci_gbk.log"), input.join("sub/hci.log")).unwrap();
+    std::fs::write(input.join("skip.bin"), "x").unwrap();
+
+    let run = |dry_run: bool| {
+        let mut cmd = worktool();
+        cmd.args(["convert", "-r", "--include", "*.log", "--from", "gbk"])
+            .arg("--output-dir")
+            .arg(dir.join("out"))
+            .arg(&input);
+        if dry_run {
+            cmd.arg("--dry-run");
+        }
+        cmd.output().unwrap()
+    };
+    let output = run(true);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("would write"));
+    assert!(!dir.join("out").exists());
+
+    let output = run(false);
+    assert!(output.status.success(), "{:?}", output);
+    assert_golden(&dir.join("out/sub/hci.log.utf8"), "hci/hci.log");
+}
+
+#[cfg(feature = "hci")]
+#[test]
+fn warnings_reach_stderr() {
+    use common::out_dir;
+
+    let dir = out_dir("cli_hci_warning");
+    let output = worktool()
+        .args(["hci", "--output-dir"])
+        .arg(&dir)
+        .arg(fixture("hardfault/no_regs.log"))
+        .env_remove("RUST_LOG")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("no CMD/EVT/ACL records"), "{}", stderr);
+}
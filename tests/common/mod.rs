@@ -0,0 +1,61 @@
+//! 集成测试共用的辅助函数
+//!
+//! 期望输出放在 `tests/fixtures` 里输入文件旁边。转换结果有意变化时用
+//! `WORKTOOL_BLESS=1 cargo test` 重新生成，再检查 diff 后提交。
+
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use worktool::core::OutputPolicy;
+
+pub fn fixture(path: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(path)
+}
+
+/// 每个测试单独的输出目录，每次运行前清空
+pub fn out_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+pub fn policy(dir: &Path) -> OutputPolicy {
+    OutputPolicy {
+        dir: Some(dir.to_path_buf()),
+        ..Default::default()
+    }
+}
+
+/// 比较 `actual` 和期望输出 `fixtures/<expected>`，按字节比较
+pub fn assert_golden(actual: &Path, expected: &str) {
+    let actual = fs::read(actual).unwrap_or_else(|e| panic!("{}: {}", actual.display(), e));
+    assert_golden_bytes(&actual, expected);
+}
+
+pub fn assert_golden_bytes(actual: &[u8], expected: &str) {
+    let expected_path = fixture(expected);
+    if std::env::var_os("WORKTOOL_BLESS").is_some() {
+        fs::write(&expected_path, actual).unwrap();
+        return;
+    }
+    let expected_bytes = fs::read(&expected_path).unwrap_or_else(|e| {
+        panic!(
+            "{}: {}, run with WORKTOOL_BLESS=1 to create it",
+            expected_path.display(),
+            e
+        )
+    });
+    if actual != expected_bytes.as_slice() {
+        panic!(
+            "output differs from {}\n--- expected\n{}\n--- actual\n{}",
+            expected,
+            String::from_utf8_lossy(&expected_bytes),
+            String::from_utf8_lossy(actual)
+        );
+    }
+}
@@ -0,0 +1,219 @@
+#![cfg(feature = "convert")]
+
+mod common;
+
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+
+use common::{assert_golden, fixture, out_dir, policy};
+use worktool::core::convert::{
+    collect, convert, convert_file, glob_match, mirror, ConvertOptions, LineEnding, TargetEncoding,
+};
+use worktool::core::encoding::FileEncoding;
+use worktool::core::{Error, Progress};
+
+fn options(target: TargetEncoding, line_ending: LineEnding) -> ConvertOptions {
+    ConvertOptions {
+        source: FileEncoding::UTF8,
+        target,
+        line_ending,
+    }
+}
+
+fn convert_str(reader: impl Read, options: &ConvertOptions) -> (Vec<u8>, u64) {
+    let mut out = Vec::new();
+    let stats = convert(reader, &mut out, options).unwrap();
+    (out, stats.unmappable)
+}
+
+#[test]
+fn gbk_to_utf8() {
+    let dir = out_dir("convert_gbk");
+    let options = ConvertOptions {
+        source: FileEncoding::GBK,
+        ..Default::default()
+    };
+    let report = convert_file(
+        &fixture("hci/hci_gbk.log"),
+        &options,
+        &policy(&dir),
+        false,
+        &Progress::default(),
+    )
+    .unwrap();
+    assert!(report.written);
+    assert_eq!(report.source.name(), "GBK");
+    assert_eq!(report.decode_error_count, 0);
+    assert_eq!(report.output, dir.join("hci_gbk.log.utf8"));
+    assert_golden(&report.output, "hci/hci.log");
+}
+
+#[test]
+fn line_endings() {
+    let input = "a\r\nb\nc\rd\r";
+    let lf = options(TargetEncoding::UTF8, LineEnding::Lf);
+    assert_eq!(convert_str(input.as_bytes(), &lf).0, b"a\nb\nc\nd\n");
+    let crlf = options(TargetEncoding::UTF8, LineEnding::CrLf);
+    assert_eq!(
+        convert_str(input.as_bytes(), &crlf).0,
+        b"a\r\nb\r\nc\r\nd\r\n"
+    );
+    let keep = options(TargetEncoding::UTF8, LineEnding::Keep);
+    assert_eq!(convert_str(input.as_bytes(), &keep).0, input.as_bytes());
+
+    let mut out = Vec::new();
+    let stats = convert(input.as_bytes(), &mut out, &keep).unwrap();
+    assert_eq!((stats.crlf, stats.lf, stats.cr), (1, 1, 2));
+
+    // `\r\n` 被读取切开时仍然算一个换行
+    let split = "a\r".as_bytes().chain("\nb".as_bytes());
+    assert_eq!(convert_str(split, &lf).0, b"a\nb");
+    // 多字节字符被切开
+    let bytes = "连接".as_bytes();
+    let split = bytes[..2].chain(&bytes[2..]);
+    assert_eq!(convert_str(split, &keep).0, bytes);
+}
+
+#[test]
+fn utf16_bom() {
+    let target = options(TargetEncoding::UTF16LE, LineEnding::Keep);
+    let (out, _) = convert_str("连接\n".as_bytes(), &target);
+    let expected: Vec<u8> = [0xFEFF_u16, 0x8FDE, 0x63A5, 0x000A]
+        .iter()
+        .flat_map(|c| c.to_le_bytes())
+        .collect();
+    assert_eq!(out, expected);
+
+    // 转回 utf8 时 BOM 被去掉
+    let dir = out_dir("convert_utf16");
+    let input = dir.join("in.txt");
+    fs::write(&input, &out).unwrap();
+    let back = ConvertOptions {
+        source: FileEncoding::UTF8,
+        ..Default::default()
+    };
+    let report = convert_file(&input, &back, &policy(&dir), false, &Progress::default()).unwrap();
+    assert_eq!(report.source.name(), "UTF-16LE");
+    assert_eq!(fs::read_to_string(report.output).unwrap(), "连接\n");
+}
+
+#[test]
+fn unmappable() {
+    let target = options(TargetEncoding::GBK, LineEnding::Keep);
+    let (out, unmappable) = convert_str("连接😀!".as_bytes(), &target);
+    assert_eq!(out, b"\xC1\xAC\xBD\xD3?!");
+    assert_eq!(unmappable, 1);
+}
+
+#[test]
+fn dry_run_writes_nothing() {
+    let dir = out_dir("convert_dry_run").join("out");
+    let options = ConvertOptions {
+        source: FileEncoding::GBK,
+        ..Default::default()
+    };
+    let report = convert_file(
+        &fixture("hci/hci_gbk.log"),
+        &options,
+        &policy(&dir),
+        true,
+        &Progress::default(),
+    )
+    .unwrap();
+    assert!(!report.written);
+    assert_eq!(report.output, dir.join("hci_gbk.log.utf8"));
+    assert_eq!(report.stats.lf, 9);
+    assert!(!dir.exists());
+}
+
+#[test]
+fn output_is_input() {
+    let dir = out_dir("convert_output_is_input");
+    let input = dir.join("a.txt");
+    fs::write(&input, "a").unwrap();
+    let mut output = policy(&dir);
+    output.template = "{name}".to_string();
+    let err = convert_file(
+        &input,
+        &ConvertOptions::default(),
+        &output,
+        true,
+        &Progress::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, Error::OutputIsInput(_)), "{:?}", err);
+}
+
+#[test]
+fn collect_files() {
+    let dir = out_dir("convert_collect");
+    for name in ["a.log", "b.TXT", "c.bin", "sub/d.log", "sub/deep/e.txt"] {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "x").unwrap();
+    }
+    let names = |files: Vec<PathBuf>| -> Vec<String> {
+        files
+            .iter()
+            .map(|path| {
+                let path = path.strip_prefix(&dir).unwrap().to_string_lossy();
+                path.replace('\\', "/")
+            })
+            .collect()
+    };
+    let roots = [dir.clone()];
+
+    assert_eq!(
+        names(collect(&roots, false, "*.log *.txt")),
+        ["a.log", "b.TXT"]
+    );
+    assert_eq!(
+        names(collect(&roots, true, "*.log;*.txt")),
+        ["a.log", "b.TXT", "sub/d.log", "sub/deep/e.txt"]
+    );
+    assert_eq!(
+        names(collect(&roots, false, "")),
+        ["a.log", "b.TXT", "c.bin"]
+    );
+    // 直接给出的文件不按通配符过滤，重复的只保留一个
+    let roots = [dir.join("c.bin"), dir.clone(), dir.join("a.log")];
+    assert_eq!(names(collect(&roots, false, "*.log")), ["c.bin", "a.log"]);
+}
+
+#[test]
+fn glob() {
+    assert!(glob_match("*.log", "hci.log"));
+    assert!(glob_match("*.LOG", "hci.log"));
+    assert!(glob_match("hci_??.log", "hci_01.log"));
+    assert!(glob_match("*", ""));
+    assert!(glob_match("a*b*c", "aXbYbZc"));
+    assert!(!glob_match("*.log", "hci.log.bak"));
+    assert!(!glob_match("hci_?.log", "hci_01.log"));
+    assert!(!glob_match("a*b", "ac"));
+}
+
+#[test]
+fn mirror_subfolders() {
+    let root = PathBuf::from("logs");
+    let file = root.join("sub").join("a.log");
+    let out = PathBuf::from("out");
+    let mirrored = mirror(&policy(&out), &[root.clone()], &file);
+    assert_eq!(mirrored.dir, Some(out.join("sub")));
+    // 没有输出目录时写到输入旁边
+    let mirrored = mirror(&Default::default(), &[root], &file);
+    assert_eq!(mirrored.dir, None);
+}
+
+#[cfg(unix)]
+#[test]
+fn collect_skips_symlink_loops() {
+    let dir = out_dir("convert_collect_loop");
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(dir.join("sub/a.log"), "x").unwrap();
+    std::os::unix::fs::symlink(&dir, dir.join("sub/loop")).unwrap();
+    assert_eq!(
+        collect(&[dir.clone()], true, "*.log"),
+        [dir.join("sub/a.log")]
+    );
+}
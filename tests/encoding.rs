@@ -0,0 +1,220 @@
+mod common;
+
+use std::fs;
+use std::io::{self, Read};
+
+use common::{fixture, out_dir, policy};
+use worktool::core::encoding::{
+    inspect, preview, save_normalized, sniff_bom, Charset, DecodeError, DecodeReader, FileEncoding,
+    PreviewSpan,
+};
+use worktool::core::{Error, Progress};
+
+const TEXT: &str = "[00:00:01.200]MSG -> 连接成功 01 02\n接続 😀\n";
+
+fn utf32(text: &str, big_endian: bool) -> Vec<u8> {
+    text.chars()
+        .flat_map(|c| {
+            if big_endian {
+                (c as u32).to_be_bytes()
+            } else {
+                (c as u32).to_le_bytes()
+            }
+        })
+        .collect()
+}
+
+/// 按 `encode` 另存 utf8 副本，返回副本的内容
+fn normalize(name: &str, bytes: &[u8], encode: FileEncoding) -> String {
+    let dir = out_dir(&format!("encoding_{}", name));
+    let input = dir.join(name);
+    fs::write(&input, bytes).unwrap();
+    let out = save_normalized(&input, &encode, &policy(&dir), &Progress::default()).unwrap();
+    String::from_utf8(fs::read(out).unwrap()).unwrap()
+}
+
+fn text(spans: &[PreviewSpan]) -> String {
+    spans
+        .iter()
+        .filter_map(|span| match span {
+            PreviewSpan::Text(text) => Some(text.as_str()),
+            PreviewSpan::Invalid(_) => None,
+        })
+        .collect()
+}
+
+#[test]
+fn inspect_gbk() {
+    let candidates = inspect(&fixture("hci/hci_gbk.log"), 5, 5).unwrap();
+    assert!(candidates.len() <= 5);
+    let gbk = candidates
+        .iter()
+        .find(|c| c.encoding == encoding_rs::GBK.into())
+        .expect("GBK should be a candidate");
+    assert!((0.0..=100.0).contains(&gbk.confidence));
+    assert!(gbk
+        .preview
+        .iter()
+        .all(|span| matches!(span, PreviewSpan::Text(_))));
+    let preview = text(&gbk.preview);
+    assert!(preview.contains("连接成功"), "{}", preview);
+    assert_eq!(preview.lines().count(), 5);
+}
+
+#[test]
+fn inspect_utf8() {
+    let candidates = inspect(&fixture("hci/hci.log"), 5, 3).unwrap();
+    assert_eq!(candidates[0].encoding, encoding_rs::UTF_8.into());
+    assert_eq!(text(&candidates[0].preview).lines().count(), 3);
+}
+
+#[test]
+fn preview_marks_invalid_bytes() {
+    let (gbk, _, _) = encoding_rs::GBK.encode("ok 连接\nnext");
+    let spans = preview(&gbk, encoding_rs::UTF_8.into(), 1);
+    assert_eq!(spans.first(), Some(&PreviewSpan::Text("ok ".to_string())));
+    let invalid: Vec<u8> = spans
+        .iter()
+        .filter_map(|span| match span {
+            PreviewSpan::Invalid(bytes) => Some(bytes.clone()),
+            PreviewSpan::Text(_) => None,
+        })
+        .flatten()
+        .collect();
+    assert_eq!(invalid, gbk[3..7]);
+    assert!(!text(&spans).contains("next"));
+}
+
+#[test]
+fn preview_truncated_tail() {
+    let (gbk, _, _) = encoding_rs::GBK.encode("编码");
+    let spans = preview(&gbk[..3], encoding_rs::GBK.into(), 5);
+    assert_eq!(spans, vec![PreviewSpan::Text("编".to_string())]);
+}
+
+#[test]
+fn explicit_encodings() {
+    let utf16 = |big_endian: bool| -> Vec<u8> {
+        TEXT.encode_utf16()
+            .flat_map(|unit| {
+                if big_endian {
+                    unit.to_be_bytes()
+                } else {
+                    unit.to_le_bytes()
+                }
+            })
+            .collect()
+    };
+    let cases = [
+        ("utf16le", utf16(false), FileEncoding::UTF16LE),
+        ("utf16be", utf16(true), FileEncoding::UTF16BE),
+        ("utf32le", utf32(TEXT, false), FileEncoding::UTF32LE),
+        ("utf32be", utf32(TEXT, true), FileEncoding::UTF32BE),
+        (
+            "gb18030",
+            encoding_rs::GB18030.encode(TEXT).0.into_owned(),
+            FileEncoding::GB18030,
+        ),
+    ];
+    for (name, bytes, encode) in cases {
+        assert_eq!(normalize(name, &bytes, encode), TEXT, "{}", name);
+    }
+
+    // Big5 和 Shift_JIS 里没有表情符号和所有汉字
+    let text = "[00:00:01.200]MSG -> 連線成功 01 02\n";
+    let big5 = encoding_rs::BIG5.encode(text).0;
+    assert_eq!(normalize("big5", &big5, FileEncoding::Big5), text);
+    let text = "[00:00:01.200]MSG -> 接続成功 01 02\n";
+    let sjis = encoding_rs::SHIFT_JIS.encode(text).0;
+    assert_eq!(normalize("sjis", &sjis, FileEncoding::ShiftJIS), text);
+}
+
+#[test]
+fn bom_wins() {
+    let mut utf16be = vec![0xFE, 0xFF];
+    utf16be.extend(TEXT.encode_utf16().flat_map(u16::to_be_bytes));
+    let mut utf32le = vec![0xFF, 0xFE, 0x00, 0x00];
+    utf32le.extend(utf32(TEXT, false));
+    let mut utf32be = vec![0x00, 0x00, 0xFE, 0xFF];
+    utf32be.extend(utf32(TEXT, true));
+    let mut utf8 = vec![0xEF, 0xBB, 0xBF];
+    utf8.extend(TEXT.as_bytes());
+
+    let cases = [
+        ("bom_utf16be", utf16be, Charset::from(encoding_rs::UTF_16BE)),
+        ("bom_utf32le", utf32le, Charset::Utf32Le),
+        ("bom_utf32be", utf32be, Charset::Utf32Be),
+        ("bom_utf8", utf8, Charset::from(encoding_rs::UTF_8)),
+    ];
+    for (name, bytes, charset) in cases {
+        assert_eq!(sniff_bom(&bytes).map(|(c, _)| c), Some(charset), "{}", name);
+        // 选错的编码不影响，BOM 也不会出现在输出里
+        assert_eq!(normalize(name, &bytes, FileEncoding::GBK), TEXT, "{}", name);
+        assert_eq!(
+            normalize(name, &bytes, FileEncoding::UTF8),
+            TEXT,
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn labels() {
+    assert_eq!(Charset::for_label("utf-32"), Some(Charset::Utf32Le));
+    assert_eq!(Charset::for_label("UTF_32BE"), Some(Charset::Utf32Be));
+    assert_eq!(
+        Charset::for_label("sjis"),
+        Some(encoding_rs::SHIFT_JIS.into())
+    );
+    assert_eq!(Charset::for_label("nope"), None);
+    // 旧版本保存的名字
+    let old: FileEncoding = serde_json::from_str("\"UTF16\"").unwrap();
+    assert_eq!(old, FileEncoding::UTF16LE);
+    for choice in FileEncoding::CHOICES {
+        let named = FileEncoding::Named(choice.charset().map_or("", Charset::name).to_string());
+        assert_eq!(named.charset(), choice.charset());
+    }
+}
+
+#[test]
+fn error_offsets() {
+    let mut bytes = b"ok ".to_vec();
+    bytes.extend_from_slice(&[0xFF, 0xFF]);
+    bytes.extend_from_slice(&encoding_rs::GBK.encode("编码 ").0);
+    bytes.push(0x81);
+    let mut reader = DecodeReader::new(bytes.as_slice(), Some(encoding_rs::GBK.into()));
+    let mut text = String::new();
+    reader.read_to_string(&mut text).unwrap();
+    assert!(reader.had_errors());
+    assert_eq!(text, "ok \u{FFFD}\u{FFFD}编码 \u{FFFD}");
+    assert_eq!(reader.error_count(), 3);
+    let offsets: Vec<u64> = reader.errors().iter().map(|e| e.offset).collect();
+    assert_eq!(offsets, [3, 4, bytes.len() as u64 - 1]);
+
+    // UTF-32：超出范围的码元和结尾不完整的码元
+    let mut bytes = utf32("ab", false);
+    bytes.extend_from_slice(&0x110000u32.to_le_bytes());
+    bytes.extend(utf32("c", false));
+    bytes.extend_from_slice(&[0x41, 0x00]);
+    let mut reader = DecodeReader::new(bytes.as_slice(), Some(Charset::Utf32Le));
+    let mut text = String::new();
+    reader.read_to_string(&mut text).unwrap();
+    assert_eq!(text, "ab\u{FFFD}c\u{FFFD}");
+    assert_eq!(
+        reader.errors(),
+        [
+            DecodeError { offset: 8, len: 4 },
+            DecodeError { offset: 16, len: 2 }
+        ]
+    );
+}
+
+#[test]
+fn only_utf8_errors_are_not_utf8() {
+    let other = io::Error::new(io::ErrorKind::InvalidData, "bad frame");
+    assert!(matches!(Error::from(other), Error::Io(_)));
+    let utf8 = String::from_utf8(vec![0xff]).unwrap_err();
+    let utf8 = io::Error::new(io::ErrorKind::InvalidData, utf8);
+    assert!(matches!(Error::from(utf8), Error::NotUtf8));
+}
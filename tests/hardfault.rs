@@ -0,0 +1,46 @@
+#![cfg(feature = "hardfault")]
+
+mod common;
+
+use common::{assert_golden, fixture, out_dir, policy};
+use worktool::core::encoding::FileEncoding;
+use worktool::core::{hardfault, Progress};
+
+#[test]
+fn parse_groups() {
+    let regs = hardfault::parse_file(
+        &fixture("hardfault/crash.log"),
+        &FileEncoding::UTF8,
+        &Progress::default(),
+    )
+    .unwrap();
+    assert_eq!(regs.len(), 2);
+    assert!(regs[0].header.contains("EPC:"), "{}", regs[0].header);
+    assert!(regs[1].header.contains("WDT_RST:"), "{}", regs[1].header);
+    // 看门狗复位没有打印的寄存器
+    assert_eq!(regs[1].regs[0], "0xXXXXXXXX");
+    assert_eq!(regs[1].regs[2], "0xXXXXXXXX");
+    assert_eq!(regs[1].regs[20], "0xXXXXXXXX");
+}
+
+#[test]
+fn report() {
+    let input = fixture("hardfault/crash.log");
+    let dir = out_dir("hardfault_report");
+    let regs = hardfault::parse_file(&input, &FileEncoding::UTF8, &Progress::default()).unwrap();
+    let out = hardfault::report_file(&input, &regs, &policy(&dir))
+        .unwrap()
+        .unwrap();
+    assert_golden(&out, "hardfault/crash.log.regs.txt");
+}
+
+#[test]
+fn no_regs() {
+    let input = fixture("hardfault/no_regs.log");
+    let dir = out_dir("hardfault_no_regs");
+    let regs = hardfault::parse_file(&input, &FileEncoding::UTF8, &Progress::default()).unwrap();
+    assert!(regs.is_empty());
+    assert!(hardfault::report_file(&input, &regs, &policy(&dir))
+        .unwrap()
+        .is_none());
+}
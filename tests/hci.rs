@@ -0,0 +1,126 @@
+#![cfg(feature = "hci")]
+
+mod common;
+
+use std::fs;
+
+use common::{assert_golden, fixture, out_dir, policy};
+use worktool::core::encoding::{self, FileEncoding};
+use worktool::core::hci::{self, Packet, PacketType};
+use worktool::core::{Error, Progress};
+
+#[test]
+fn btsnoop() {
+    let dir = out_dir("hci_btsnoop");
+    let out = hci::convert_file(
+        &fixture("hci/hci.log"),
+        &FileEncoding::UTF8,
+        &policy(&dir),
+        &Progress::default(),
+    )
+    .unwrap();
+    assert_eq!(out, dir.join("hci.log.btsnoop"));
+    assert_golden(&out, "hci/hci.log.btsnoop");
+}
+
+#[test]
+fn btsnoop_gbk() {
+    let input = fixture("hci/hci_gbk.log");
+    let before = fs::read(&input).unwrap();
+    let dir = out_dir("hci_gbk");
+    let out = hci::convert_file(
+        &input,
+        &FileEncoding::GBK,
+        &policy(&dir),
+        &Progress::default(),
+    )
+    .unwrap();
+    // 中文不在记录里，结果和 utf8 版本一样
+    assert_golden(&out, "hci/hci.log.btsnoop");
+    // 输入文件不变，也没有留下备份
+    assert_eq!(fs::read(&input).unwrap(), before);
+    assert!(!input.with_extension("log.bak").exists());
+}
+
+#[test]
+fn gbk_as_utf8() {
+    let dir = out_dir("hci_gbk_as_utf8");
+    let err = hci::convert_file(
+        &fixture("hci/hci_gbk.log"),
+        &FileEncoding::UTF8,
+        &policy(&dir),
+        &Progress::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, Error::NotUtf8), "{:?}", err);
+}
+
+#[test]
+fn parse_records() {
+    let packets = hci::parse(concat!(
+        "[00:00:01.100]CMD => 01 03 0C 00 [00:00:01.105]EVT <= 04 0E 04 01 03 0C 00\n",
+        // 没有包类型时补上，opcode 0x0401 的第一个字节碰巧是 01
+        "[00:00:02.000]CMD => 01 04 05 33 8B 9E 08 00\r\n",
+        "[00:00:02.001]CMD => 03 0C 00 debug 12\n",
+        "[00:00:02.002]ACL <= 40 20 01 00 AB\n",
+        "[00:00:02.003]MSG -> 01 02\n",
+        "ACL => 02 40 20 01 00 AB\n",
+        "[00:00:02.004]EVT <= zz\n",
+    ));
+    let packet = |packet_type, received, time, data: &[u8]| Packet {
+        packet_type,
+        received,
+        time,
+        data: data.to_vec(),
+    };
+    assert_eq!(
+        packets,
+        [
+            packet(PacketType::Command, false, 1_100_000, &[1, 3, 0x0C, 0]),
+            packet(
+                PacketType::Event,
+                true,
+                1_105_000,
+                &[4, 0x0E, 4, 1, 3, 0x0C, 0]
+            ),
+            packet(
+                PacketType::Command,
+                false,
+                2_000_000,
+                &[1, 1, 4, 5, 0x33, 0x8B, 0x9E, 8, 0]
+            ),
+            packet(PacketType::Command, false, 2_001_000, &[1, 3, 0x0C, 0]),
+            packet(
+                PacketType::Acl,
+                true,
+                2_002_000,
+                &[2, 0x40, 0x20, 1, 0, 0xAB]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn midnight() {
+    let packets = hci::parse(concat!(
+        "[23:59:59.999]CMD => 01 03 0C 00\n",
+        "[00:00:00.001]EVT <= 04 0E 04 01 03 0C 00\n",
+    ));
+    let day = 24 * 3600 * 1_000_000;
+    assert_eq!(packets[0].time, day - 1000);
+    assert_eq!(packets[1].time, day + 1000);
+}
+
+#[test]
+fn save_utf8_copy() {
+    let dir = out_dir("hci_save_normalized");
+    let out = encoding::save_normalized(
+        &fixture("hci/hci_gbk.log"),
+        &FileEncoding::GBK,
+        &policy(&dir),
+        &Progress::default(),
+    )
+    .unwrap();
+    assert_eq!(out, dir.join("hci_gbk.log.utf8"));
+    assert_golden(&out, "hci/hci.log");
+}
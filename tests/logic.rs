@@ -0,0 +1,142 @@
+#![cfg(feature = "logic")]
+
+mod common;
+
+use std::fs;
+
+use common::{assert_golden, fixture, out_dir, policy};
+use worktool::core::logic::{
+    iis_file, spi_file, uart_file, LogicIISArgs, LogicSpiArgs, LogicUARTArgs, SpiConvType,
+};
+use worktool::core::{Error, Progress};
+
+fn spi(conv_type: SpiConvType, input: &str, out: &str) -> Vec<std::path::PathBuf> {
+    let dir = out_dir(out);
+    spi_file(
+        &LogicSpiArgs { conv_type },
+        &fixture(input),
+        &policy(&dir),
+        &Progress::default(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn spi_raw() {
+    let outputs = spi(SpiConvType::RAW, "logic/spi.csv", "spi_raw");
+    assert_eq!(outputs.len(), 1);
+    assert_golden(&outputs[0], "logic/spi.csv.out");
+}
+
+#[test]
+fn spi_txt() {
+    let outputs = spi(SpiConvType::TXT, "logic/spi.csv", "spi_txt");
+    assert_eq!(outputs.len(), 1);
+    assert_golden(&outputs[0], "logic/spi.csv.txt");
+}
+
+#[test]
+fn spi_bluetrum() {
+    let outputs = spi(
+        SpiConvType::BluetrumVoiceDump,
+        "logic/bluetrum.csv",
+        "spi_bluetrum",
+    );
+    assert_eq!(outputs.len(), 3);
+    for (output, expected) in outputs.iter().zip([
+        "logic/bluetrum.csv.out1",
+        "logic/bluetrum.csv.out2",
+        "logic/bluetrum.csv.out3",
+    ]) {
+        assert_golden(output, expected);
+    }
+}
+
+#[test]
+fn iis() {
+    let dir = out_dir("iis");
+    let outputs = iis_file(
+        &LogicIISArgs::default(),
+        &fixture("logic/iis.csv"),
+        &policy(&dir),
+        &Progress::default(),
+    )
+    .unwrap();
+    assert_golden(&outputs[0], "logic/iis.csv.out");
+}
+
+#[test]
+fn uart() {
+    let dir = out_dir("uart");
+    let outputs = uart_file(
+        &LogicUARTArgs::default(),
+        &fixture("logic/uart.csv"),
+        &policy(&dir),
+        &Progress::default(),
+    )
+    .unwrap();
+    assert_golden(&outputs[0], "logic/uart.csv.txt");
+}
+
+#[test]
+fn wrong_protocol() {
+    let dir = out_dir("wrong_protocol");
+    let err = spi_file(
+        &LogicSpiArgs::default(),
+        &fixture("logic/iis.csv"),
+        &policy(&dir),
+        &Progress::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, Error::InvalidHeader { .. }), "{:?}", err);
+}
+
+#[test]
+fn bluetrum_outputs_need_format() {
+    let dir = out_dir("bluetrum_same_name");
+    let mut output = policy(&dir);
+    output.template = "{stem}.bin".to_string();
+    let err = spi_file(
+        &LogicSpiArgs {
+            conv_type: SpiConvType::BluetrumVoiceDump,
+        },
+        &fixture("logic/bluetrum.csv"),
+        &output,
+        &Progress::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, Error::SameOutputName(_)), "{:?}", err);
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+}
+
+#[test]
+fn output_name_stays_in_dir() {
+    let dir = out_dir("output_name_outside");
+    let mut output = policy(&dir);
+    output.template = "../{name}.{format}".to_string();
+    let err = spi_file(
+        &LogicSpiArgs::default(),
+        &fixture("logic/spi.csv"),
+        &output,
+        &Progress::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, Error::InvalidOutputName(_)), "{:?}", err);
+}
+
+#[test]
+fn invalid_value_leaves_no_output() {
+    let dir = out_dir("spi_invalid_value");
+    let input = dir.join("a.csv");
+    fs::write(&input, "Time [s],Packet ID,MOSI,MISO\n0.1,0,0xZZ,0x00\n").unwrap();
+    let err = spi_file(
+        &LogicSpiArgs::default(),
+        &input,
+        &policy(&dir),
+        &Progress::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, Error::InvalidValue { .. }), "{:?}", err);
+    // 失败时不留下空的输出，否则按跳过处理时重试会跳过这个文件
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+}
@@ -0,0 +1,261 @@
+//! 解析函数的性质测试：任意输入都只能返回错误，不能 panic
+//!
+//! 失败时 proptest 会把缩小后的输入打印出来，可以加到 `tests/fixtures` 里。
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use proptest::prelude::*;
+
+/// 随便拼出来的行，带上逗号、`0x` 和空格，容易撞到解析的边界
+#[cfg(any(feature = "logic", feature = "hardfault"))]
+fn messy_lines() -> impl Strategy<Value = Vec<String>> {
+    prop::collection::vec("[0-9a-fA-FxX, .:\\-]{0,24}|\\PC{0,16}", 0..40)
+}
+
+#[cfg(feature = "logic")]
+mod logic {
+    use super::*;
+    use worktool::core::logic::{
+        iis, spi_bluetrum, spi_raw, spi_txt, uart, LogicIISArgs, LogicUARTArgs,
+        KINGST_IIS_FILE_FORMAT, KINGST_SPI_FILE_FORMAT, KINGST_UART_FILE_FORMAT,
+    };
+
+    fn csv(header: &str, lines: &[String]) -> Vec<u8> {
+        let mut csv = format!("{}\r\n", header);
+        for line in lines {
+            csv.push_str(line);
+            csv.push_str("\r\n");
+        }
+        csv.into_bytes()
+    }
+
+    fn spi_csv(bytes: &[u8]) -> Vec<u8> {
+        let lines: Vec<String> = bytes
+            .iter()
+            .enumerate()
+            .map(|(i, b)| format!("{:.9},0,0x{:02X},", i as f64 * 0.0000125, b))
+            .collect();
+        csv(KINGST_SPI_FILE_FORMAT, &lines)
+    }
+
+    proptest! {
+        #[test]
+        fn spi_raw_roundtrip(bytes in prop::collection::vec(any::<u8>(), 0..256)) {
+            let mut out = Vec::new();
+            spi_raw(spi_csv(&bytes).as_slice(), &mut out).unwrap();
+            prop_assert_eq!(out, bytes);
+        }
+
+        #[test]
+        fn iis_roundtrip(samples in prop::collection::vec(any::<u16>(), 0..128)) {
+            let lines: Vec<String> = samples
+                .iter()
+                .enumerate()
+                .map(|(i, s)| format!("{},{},0x{:04X}", i, i % 2, s))
+                .collect();
+            let mut out = Vec::new();
+            iis(&LogicIISArgs::default(), csv(KINGST_IIS_FILE_FORMAT, &lines).as_slice(), &mut out)
+                .unwrap();
+            let expected: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+            prop_assert_eq!(out, expected);
+        }
+
+        #[test]
+        fn spi_txt_roundtrip(bytes in prop::collection::vec(any::<u8>(), 0..256)) {
+            let mut out = Vec::new();
+            spi_txt(spi_csv(&bytes).as_slice(), &mut out).unwrap();
+            let parsed: Vec<u8> = String::from_utf8(out)
+                .unwrap()
+                .split_whitespace()
+                .map(|b| u8::from_str_radix(b, 16).unwrap())
+                .collect();
+            prop_assert_eq!(parsed, bytes);
+        }
+
+        /// 蓝讯帧里的数据不会比输入多，帧长度为 0 也不会卡住
+        #[test]
+        fn bluetrum_any_bytes(bytes in prop::collection::vec(any::<u8>(), 0..512)) {
+            let mut outs = [Vec::new(), Vec::new(), Vec::new()];
+            spi_bluetrum(spi_csv(&bytes).as_slice(), &mut outs).unwrap();
+            prop_assert!(outs.iter().map(Vec::len).sum::<usize>() <= bytes.len());
+        }
+
+        #[test]
+        fn kingst_messy_lines(lines in messy_lines()) {
+            let mut out = Vec::new();
+            let _ = spi_raw(csv(KINGST_SPI_FILE_FORMAT, &lines).as_slice(), &mut out);
+            let _ = spi_txt(csv(KINGST_SPI_FILE_FORMAT, &lines).as_slice(), &mut out);
+            let mut outs = [Vec::new(), Vec::new(), Vec::new()];
+            let _ = spi_bluetrum(csv(KINGST_SPI_FILE_FORMAT, &lines).as_slice(), &mut outs);
+            let _ = iis(
+                &LogicIISArgs::default(),
+                csv(KINGST_IIS_FILE_FORMAT, &lines).as_slice(),
+                &mut out,
+            );
+            let _ = uart(
+                &LogicUARTArgs::default(),
+                csv(KINGST_UART_FILE_FORMAT, &lines).as_slice(),
+                &mut out,
+            );
+        }
+
+        #[test]
+        fn kingst_any_bytes(bytes in prop::collection::vec(any::<u8>(), 0..512)) {
+            let mut out = Vec::new();
+            let _ = spi_raw(bytes.as_slice(), &mut out);
+            let mut input = csv(KINGST_SPI_FILE_FORMAT, &[]);
+            input.extend_from_slice(&bytes);
+            let _ = spi_raw(input.as_slice(), &mut out);
+        }
+    }
+}
+
+#[cfg(feature = "hardfault")]
+mod hardfault {
+    use super::*;
+    use worktool::core::hardfault::parse;
+
+    fn hex_words() -> impl Strategy<Value = Vec<String>> {
+        prop::collection::vec("[0-9a-fA-F]{1,8}|0x[0-9a-f]{1,8}|zz|", 0..80)
+    }
+
+    proptest! {
+        #[test]
+        fn any_bytes(bytes in prop::collection::vec(any::<u8>(), 0..1024)) {
+            let _ = parse(bytes.as_slice());
+        }
+
+        /// 一行里的数值比寄存器多也只解析出一组
+        #[test]
+        fn long_register_lines(words in hex_words(), wdt in any::<bool>()) {
+            let header = if wdt { "WDT_RST: 0x1" } else { "ERR: 0x2 EPC: 0x10001234" };
+            let log = format!("{}\n{}\n", header, words.join(" "));
+            let regs = parse(log.as_bytes()).unwrap();
+            prop_assert!(regs.len() <= 1);
+        }
+
+        #[test]
+        fn messy_log(lines in messy_lines(), headers in prop::collection::vec(0..3usize, 0..8)) {
+            let mut log = String::new();
+            for (i, line) in lines.iter().enumerate() {
+                match headers.get(i) {
+                    Some(0) => log.push_str("ERR: 0x2 EPC: 0x0\n"),
+                    Some(1) => log.push_str("WDT_RST: 0x1\n"),
+                    _ => {}
+                }
+                log.push_str(line);
+                log.push('\n');
+            }
+            let regs = parse(log.as_bytes()).unwrap();
+            prop_assert!(regs.len() <= headers.len());
+        }
+    }
+}
+
+#[cfg(feature = "hci")]
+mod hci {
+    use super::*;
+    use worktool::core::hci::parse;
+
+    proptest! {
+        #[test]
+        fn packets_start_with_type(content in "(\\[[0-9]{2}:[0-9]{2}:[0-9]{2}\\.[0-9]{3}\\]|CMD|EVT|ACL| => | <= | [0-9A-F]{2}| [0-9A-F]{1,3}|\\PC{0,8}|\r?\n){0,40}") {
+            for packet in parse(&content) {
+                prop_assert_eq!(packet.data[0], packet.packet_type as u8);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "analyze")]
+mod analyze {
+    use super::*;
+    use worktool::core::analyze::process_logic_data;
+
+    proptest! {
+        #[test]
+        fn any_bytes(bytes in prop::collection::vec(any::<u8>(), 0..1024)) {
+            let _ = process_logic_data(bytes.as_slice());
+        }
+
+        #[test]
+        fn messages_have_colon(chars in prop::collection::vec("[A-Z:a-z0-9 ]|NUL|LF |", 0..200)) {
+            let mut csv = String::from("Time [s],Packet ID,MOSI,MISO\n");
+            for (i, c) in chars.iter().enumerate() {
+                csv.push_str(&format!("{},0,{},\n", i, c));
+            }
+            let messages = process_logic_data(csv.as_bytes()).unwrap();
+            prop_assert!(messages.iter().all(|m| m.content.contains(':')));
+        }
+    }
+}
+
+mod encoding {
+    use std::io::Read;
+
+    use super::*;
+    use worktool::core::encoding::{Charset, DecodeReader};
+
+    /// 每次最多读出 `step` 个字节，模拟多字节字符被切开
+    struct Trickle<'a> {
+        data: &'a [u8],
+        step: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.step.min(buf.len()).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn gbk_roundtrip(text in "[a-z0-9 \n中文测试编码，。]{0,200}", step in 1..8usize, read in 1..16usize) {
+            let (gbk, _, _) = encoding_rs::GBK.encode(&text);
+            let mut reader = DecodeReader::new(Trickle { data: &gbk, step }, Some(encoding_rs::GBK.into()));
+            let mut decoded = Vec::new();
+            let mut buf = vec![0; read];
+            loop {
+                let n = reader.read(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                decoded.extend_from_slice(&buf[..n]);
+            }
+            prop_assert!(!reader.had_errors());
+            prop_assert_eq!(String::from_utf8(decoded).unwrap(), text);
+        }
+
+        /// 码元被切开、带不带 BOM 都能还原
+        #[test]
+        fn utf32_roundtrip(text in "\\PC{0,100}", big_endian in any::<bool>(), bom in any::<bool>(), step in 1..8usize) {
+            let mut bytes = Vec::new();
+            for c in bom.then_some('\u{FEFF}').into_iter().chain(text.chars()) {
+                if big_endian {
+                    bytes.extend_from_slice(&(c as u32).to_be_bytes());
+                } else {
+                    bytes.extend_from_slice(&(c as u32).to_le_bytes());
+                }
+            }
+            let charset = if big_endian { Charset::Utf32Be } else { Charset::Utf32Le };
+            let mut reader = DecodeReader::new(Trickle { data: &bytes, step }, Some(charset));
+            let mut decoded = String::new();
+            reader.read_to_string(&mut decoded).unwrap();
+            prop_assert!(!reader.had_errors());
+            prop_assert_eq!(decoded, text);
+        }
+
+        #[test]
+        fn any_bytes_decode_to_utf8(bytes in prop::collection::vec(any::<u8>(), 0..512), step in 1..8usize) {
+            for charset in [encoding_rs::GBK.into(), Charset::Utf32Le] {
+                let mut reader = DecodeReader::new(Trickle { data: &bytes, step }, Some(charset));
+                let mut decoded = Vec::new();
+                reader.read_to_end(&mut decoded).unwrap();
+                prop_assert!(String::from_utf8(decoded).is_ok());
+            }
+        }
+    }
+}
@@ -0,0 +1,48 @@
+#![cfg(feature = "convert")]
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::out_dir;
+use worktool::core::convert::{from_root, relative_to_root};
+use worktool::workspace::Workspace;
+
+fn write_logs(base: &Path) {
+    fs::create_dir_all(base.join("logs/sub")).unwrap();
+    fs::write(base.join("logs/sub/a.log"), "a").unwrap();
+}
+
+/// 工作区和数据一起拷到别的目录，输入和单独指定编码的文件都跟着换到新目录
+#[test]
+fn workspace_moves_with_its_inputs() {
+    let a = out_dir("workspace_a");
+    write_logs(&a);
+    let a = fs::canonicalize(&a).unwrap();
+    let roots = vec![a.join("logs")];
+    let (root, path) = relative_to_root(&roots, &a.join("logs/sub/a.log")).unwrap();
+    let options = serde_json::json!({
+        "recursive": true,
+        "overrides": [{ "root": root, "path": path, "encoding": "GBK" }],
+    });
+    let mut workspace = Workspace::default();
+    workspace.push("convert", roots, &options.to_string());
+    workspace.save(&a.join("worktool.json")).unwrap();
+
+    let saved = fs::read_to_string(a.join("worktool.json")).unwrap();
+    assert!(!saved.contains(&*a.to_string_lossy()), "{}", saved);
+
+    let b = out_dir("workspace_b");
+    write_logs(&b);
+    fs::write(b.join("worktool.json"), saved).unwrap();
+    let b = fs::canonicalize(&b).unwrap();
+
+    let workspace = Workspace::load(&b.join("worktool.json")).unwrap();
+    let tool = &workspace.tools[0];
+    assert_eq!(tool.inputs, [b.join("logs")]);
+    assert_eq!(tool.options, options);
+    let override_path = from_root(&tool.inputs, root, &path).unwrap();
+    assert_eq!(override_path, b.join("logs/sub/a.log"));
+    assert!(override_path.is_file());
+}